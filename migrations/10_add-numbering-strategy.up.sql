@@ -0,0 +1,3 @@
+ALTER TABLE template_channels
+    ADD COLUMN numbering TEXT NOT NULL DEFAULT 'lowest_free'
+    CHECK (numbering IN ('lowest_free', 'monotonic', 'compact'));
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS template_channels_numbering_group_index;
+ALTER TABLE template_channels DROP COLUMN IF EXISTS numbering_group;
@@ -0,0 +1,3 @@
+ALTER TABLE template_channels ADD COLUMN numbering_group TEXT;
+
+CREATE INDEX template_channels_numbering_group_index ON template_channels (guild_id, numbering_group) WHERE numbering_group IS NOT NULL;
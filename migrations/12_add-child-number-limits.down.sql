@@ -0,0 +1,3 @@
+ALTER TABLE template_channels
+    DROP COLUMN IF EXISTS max_children,
+    DROP COLUMN IF EXISTS first_child_number;
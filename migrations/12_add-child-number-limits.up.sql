@@ -0,0 +1,3 @@
+ALTER TABLE template_channels
+    ADD COLUMN first_child_number BIGINT NOT NULL DEFAULT 1 CHECK (first_child_number >= 0),
+    ADD COLUMN max_children BIGINT CHECK (max_children > 0);
@@ -0,0 +1,3 @@
+ALTER TABLE guild_settings
+    ADD COLUMN locale TEXT NOT NULL DEFAULT 'en'
+    CHECK (locale IN ('en', 'sv', 'de'));
@@ -0,0 +1,2 @@
+ALTER TABLE child_channels DROP COLUMN IF EXISTS empty_since;
+ALTER TABLE template_channels DROP COLUMN IF EXISTS grace_period;
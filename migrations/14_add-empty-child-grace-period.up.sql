@@ -0,0 +1,4 @@
+ALTER TABLE template_channels
+    ADD COLUMN grace_period BIGINT NOT NULL DEFAULT 0 CHECK (grace_period >= 0);
+ALTER TABLE child_channels
+    ADD COLUMN empty_since TIMESTAMPTZ;
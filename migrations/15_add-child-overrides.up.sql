@@ -0,0 +1 @@
+ALTER TABLE child_channels ADD COLUMN overrides JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,2 @@
+ALTER TABLE child_channels DROP COLUMN IF EXISTS owner_left_at;
+ALTER TABLE template_channels DROP COLUMN IF EXISTS ownership_transfer;
@@ -0,0 +1,5 @@
+ALTER TABLE template_channels
+    ADD COLUMN ownership_transfer TEXT NOT NULL DEFAULT 'automatic'
+    CHECK (ownership_transfer IN ('automatic', 'claim'));
+ALTER TABLE child_channels
+    ADD COLUMN owner_left_at TIMESTAMPTZ;
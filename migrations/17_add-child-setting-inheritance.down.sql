@@ -0,0 +1 @@
+ALTER TABLE template_channels DROP COLUMN IF EXISTS uninherited;
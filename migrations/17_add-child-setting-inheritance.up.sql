@@ -0,0 +1,3 @@
+ALTER TABLE template_channels
+    ADD COLUMN uninherited TEXT[] NOT NULL DEFAULT '{}'
+    CHECK (uninherited <@ ARRAY['bitrate', 'rtc_region', 'video_quality', 'nsfw', 'permissions']);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS child_channels_pool_entry_index;
+CREATE UNIQUE INDEX child_channels_pool_entry_index ON child_channels (parent_id, pool_name, pool_entry) WHERE pool_entry IS NOT NULL;
@@ -0,0 +1,12 @@
+-- Children of different parents could share an entry before, so all but one of
+-- them fall back to their number.
+UPDATE child_channels SET pool_entry = NULL
+WHERE pool_entry IS NOT NULL AND child_id NOT IN (
+    SELECT DISTINCT ON (guild_id, pool_name, pool_entry) child_id
+    FROM child_channels
+    WHERE pool_entry IS NOT NULL
+    ORDER BY guild_id, pool_name, pool_entry, child_id
+);
+
+DROP INDEX child_channels_pool_entry_index;
+CREATE UNIQUE INDEX child_channels_pool_entry_index ON child_channels (guild_id, pool_name, pool_entry) WHERE pool_entry IS NOT NULL;
@@ -0,0 +1 @@
+ALTER TABLE child_channels DROP COLUMN IF EXISTS owner_id;
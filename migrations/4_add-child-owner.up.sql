@@ -0,0 +1 @@
+ALTER TABLE child_channels ADD COLUMN owner_id BIGINT;
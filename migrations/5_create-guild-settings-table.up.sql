@@ -0,0 +1,4 @@
+CREATE TABLE guild_settings (
+    guild_id BIGINT PRIMARY KEY NOT NULL,
+    time_zone TEXT NOT NULL DEFAULT 'UTC'
+);
@@ -0,0 +1,6 @@
+CREATE TABLE template_variables (
+    guild_id BIGINT NOT NULL,
+    variable_name TEXT NOT NULL,
+    variable_value TEXT NOT NULL,
+    PRIMARY KEY (guild_id, variable_name)
+);
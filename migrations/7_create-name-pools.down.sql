@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS child_channels_pool_entry_index;
+ALTER TABLE child_channels DROP COLUMN IF EXISTS pool_entry;
+ALTER TABLE child_channels DROP COLUMN IF EXISTS pool_name;
+DROP TABLE IF EXISTS name_pools;
@@ -0,0 +1,11 @@
+CREATE TABLE name_pools (
+    guild_id BIGINT NOT NULL,
+    pool_name TEXT NOT NULL,
+    entries TEXT[] NOT NULL,
+    PRIMARY KEY (guild_id, pool_name)
+);
+
+ALTER TABLE child_channels ADD COLUMN pool_name TEXT;
+ALTER TABLE child_channels ADD COLUMN pool_entry TEXT;
+
+CREATE UNIQUE INDEX child_channels_pool_entry_index ON child_channels (parent_id, pool_name, pool_entry) WHERE pool_entry IS NOT NULL;
@@ -0,0 +1 @@
+ALTER TABLE child_channels DROP COLUMN IF EXISTS render_state;
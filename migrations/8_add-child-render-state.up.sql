@@ -0,0 +1 @@
+ALTER TABLE child_channels ADD COLUMN render_state JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,4 @@
+ALTER TABLE template_channels
+    DROP COLUMN IF EXISTS status_template,
+    DROP COLUMN IF EXISTS bitrate_template,
+    DROP COLUMN IF EXISTS user_limit_template;
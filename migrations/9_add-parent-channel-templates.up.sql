@@ -0,0 +1,4 @@
+ALTER TABLE template_channels
+    ADD COLUMN status_template TEXT,
+    ADD COLUMN bitrate_template TEXT,
+    ADD COLUMN user_limit_template TEXT;
@@ -1,3 +1,4 @@
+use chrono_tz::Tz;
 use eyre::{
     eyre,
     Result,
@@ -15,6 +16,8 @@ use sqlx::{
 };
 use tracing::info;
 
+use crate::locale::Locale;
+
 pub(crate) async fn clean_left_guild_from_db(executor: &PgPool, guild_id: GuildId) -> Result<()> {
     info!("Cleaning guild with ID `{guild_id}` from database!");
 
@@ -53,6 +56,54 @@ pub(crate) async fn clean_left_guild_from_db(executor: &PgPool, guild_id: GuildI
         );
     })?;
 
+    query!(
+        "DELETE FROM name_pools WHERE guild_id = $1;",
+        guild_id.get() as i64
+    )
+    .execute(&mut *transaction)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Deleting name pools from database for guild with ID `{guild_id}` failed!")
+    })
+    .map(|res| {
+        info!(
+            "Finished deleting {} rows from name_pools",
+            res.rows_affected()
+        );
+    })?;
+
+    query!(
+        "DELETE FROM template_variables WHERE guild_id = $1;",
+        guild_id.get() as i64
+    )
+    .execute(&mut *transaction)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Deleting template variables from database for guild with ID `{guild_id}` failed!")
+    })
+    .map(|res| {
+        info!(
+            "Finished deleting {} rows from template_variables",
+            res.rows_affected()
+        );
+    })?;
+
+    query!(
+        "DELETE FROM guild_settings WHERE guild_id = $1;",
+        guild_id.get() as i64
+    )
+    .execute(&mut *transaction)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Deleting guild settings from database for guild with ID `{guild_id}` failed!")
+    })
+    .map(|res| {
+        info!(
+            "Finished deleting {} rows from guild_settings",
+            res.rows_affected()
+        );
+    })?;
+
     transaction.commit().await.wrap_err_with(|| {
         eyre!("Committing transaction for deleting guild {guild_id} from database failed!")
     })?;
@@ -62,6 +113,7 @@ pub(crate) async fn clean_left_guild_from_db(executor: &PgPool, guild_id: GuildI
     Ok(())
 }
 
+#[allow(clippy::too_many_lines)]
 pub(crate) async fn clean_inactive_guilds_from_db(
     executor: &PgPool,
     guilds_to_keep: &[i64],
@@ -109,6 +161,63 @@ pub(crate) async fn clean_inactive_guilds_from_db(
         );
     })?;
 
+    query!(
+        "DELETE FROM name_pools WHERE NOT guild_id = ANY($1);",
+        guilds_to_keep
+    )
+    .execute(&mut *transaction)
+    .await
+    .wrap_err_with(|| {
+        eyre!(
+            "Deleting name pools from database for inactive guilds failed! Active guild ids were \
+             {guilds_to_keep:?}!"
+        )
+    })
+    .map(|res| {
+        info!(
+            "Finished deleting {} rows from name_pools",
+            res.rows_affected()
+        );
+    })?;
+
+    query!(
+        "DELETE FROM template_variables WHERE NOT guild_id = ANY($1);",
+        guilds_to_keep
+    )
+    .execute(&mut *transaction)
+    .await
+    .wrap_err_with(|| {
+        eyre!(
+            "Deleting template variables from database for inactive guilds failed! Active guild \
+             ids were {guilds_to_keep:?}!"
+        )
+    })
+    .map(|res| {
+        info!(
+            "Finished deleting {} rows from template_variables",
+            res.rows_affected()
+        );
+    })?;
+
+    query!(
+        "DELETE FROM guild_settings WHERE NOT guild_id = ANY($1);",
+        guilds_to_keep
+    )
+    .execute(&mut *transaction)
+    .await
+    .wrap_err_with(|| {
+        eyre!(
+            "Deleting guild settings from database for inactive guilds failed! Active guild ids \
+             were {guilds_to_keep:?}!"
+        )
+    })
+    .map(|res| {
+        info!(
+            "Finished deleting {} rows from guild_settings",
+            res.rows_affected()
+        );
+    })?;
+
     transaction.commit().await.wrap_err_with(|| {
         eyre!("Committing transaction for deleting inactive guilds from database failed!")
     })?;
@@ -117,3 +226,78 @@ pub(crate) async fn clean_inactive_guilds_from_db(
 
     Ok(())
 }
+
+/// Returns the time zone time directives are rendered in for a guild, which is
+/// UTC unless it has been changed.
+pub(crate) async fn get_time_zone(executor: &PgPool, guild_id: GuildId) -> Result<Tz> {
+    let Some(row) = query!(
+        "SELECT time_zone FROM guild_settings WHERE guild_id = $1;",
+        guild_id.get() as i64
+    )
+    .fetch_optional(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Getting time zone from database for guild with ID `{guild_id}` failed!")
+    })?
+    else {
+        return Ok(Tz::default());
+    };
+    row.time_zone
+        .parse()
+        .map_err(|e| eyre!("{e}"))
+        .wrap_err_with(|| {
+            eyre!(
+                "Time zone `{}` stored in database is invalid!",
+                row.time_zone
+            )
+        })
+}
+
+pub(crate) async fn set_time_zone(
+    executor: &PgPool,
+    guild_id: GuildId,
+    time_zone: Tz,
+) -> Result<()> {
+    query!(
+        "INSERT INTO guild_settings (guild_id, time_zone) VALUES ($1, $2) ON CONFLICT (guild_id) \
+         DO UPDATE SET time_zone = $2;",
+        guild_id.get() as i64,
+        time_zone.name()
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| eyre!("Setting time zone in database for guild with ID `{guild_id}` failed!"))
+    .map(|_| ())
+}
+
+/// Returns the locale a guild's number words and bot replies are in, which is
+/// English unless it has been changed.
+pub(crate) async fn get_locale(executor: &PgPool, guild_id: GuildId) -> Result<Locale> {
+    let Some(row) = query!(
+        "SELECT locale FROM guild_settings WHERE guild_id = $1;",
+        guild_id.get() as i64
+    )
+    .fetch_optional(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Getting locale from database for guild with ID `{guild_id}` failed!")
+    })?
+    else {
+        return Ok(Locale::default());
+    };
+    Locale::from_code(&row.locale)
+        .ok_or_else(|| eyre!("Locale `{}` stored in database is invalid!", row.locale))
+}
+
+pub(crate) async fn set_locale(executor: &PgPool, guild_id: GuildId, locale: Locale) -> Result<()> {
+    query!(
+        "INSERT INTO guild_settings (guild_id, locale) VALUES ($1, $2) ON CONFLICT (guild_id) DO \
+         UPDATE SET locale = $2;",
+        guild_id.get() as i64,
+        locale.code()
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| eyre!("Setting locale in database for guild with ID `{guild_id}` failed!"))
+    .map(|_| ())
+}
@@ -2,11 +2,21 @@ use std::{
     env::var,
     ops::Not,
     sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
         Arc,
         LazyLock,
     },
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
+use chrono::Utc;
+use chrono_tz::Tz;
 use color_eyre::Section;
 use eyre::{
     eyre,
@@ -23,15 +33,20 @@ use serde_json::{
 use serenity::{
     all::{
         ActivityData,
+        ActivityType,
+        Cache,
         ChannelId,
+        CreateMessage,
         Guild,
         GuildChannel,
         GuildId,
         Member,
         Message,
         OnlineStatus,
+        Presence,
         Ready,
         UnavailableGuild,
+        UserId,
         VoiceState,
     },
     client::{
@@ -40,7 +55,10 @@ use serenity::{
     },
 };
 use sqlx::postgres::PgPoolOptions;
-use tokio::sync::RwLock;
+use tokio::{
+    sync::RwLock,
+    time::MissedTickBehavior,
+};
 use tracing::Instrument;
 #[allow(unused_imports)]
 use tracing::{
@@ -51,9 +69,13 @@ use tracing::{
     info_span,
     trace,
     trace_span,
+    warn,
 };
 use voice_channels::db::{
+    is_at_max_children,
     Child,
+    ChildSetting,
+    OwnershipTransfer,
     Parent,
 };
 
@@ -61,8 +83,15 @@ use crate::{
     db::{
         clean_inactive_guilds_from_db,
         clean_left_guild_from_db,
+        get_locale,
+        get_time_zone,
     },
     get_db_handle,
+    get_template_cache,
+    locale::{
+        Locale,
+        Reply,
+    },
     util::{
         get_value,
         CacheExt,
@@ -70,10 +99,18 @@ use crate::{
     voice_channels::{
         self,
         db::Children,
-        parser::parse_template,
+        parser::Template,
         updater::{
-            SerenityContextWrapper,
-            UpdaterContext,
+            max_bitrate,
+            most_common_game,
+            render_channel,
+            set_voice_status,
+            update_channel,
+            ChannelOverrides,
+            ChannelTemplates,
+            OwnerInfo,
+            RenderContext,
+            RenderState,
         },
     },
     ClientID,
@@ -83,10 +120,22 @@ use crate::{
     FrameworkContext,
     GuildChannels,
     HashMap,
+    ParsedTemplates,
+    PendingDeletions,
+    TrackedVoiceState,
     VoiceStates,
     CLIENT_ID,
 };
 
+/// How often children with time directives are re-rendered. Discord only
+/// allows renaming a channel twice every ten minutes, so refreshing more often
+/// would just queue up rate limited requests.
+const TIME_REFRESH_INTERVAL: Duration = Duration::from_mins(5);
+
+/// Whether the loop refreshing children with time directives has been started.
+/// `on_ready` runs again for every new gateway session, but one loop is enough.
+static TIME_REFRESH_STARTED: AtomicBool = AtomicBool::new(false);
+
 pub(crate) async fn delete_parent_and_children(
     ctx: &SerenityContext,
     guild_id: GuildId,
@@ -120,6 +169,10 @@ pub(crate) async fn delete_parent_and_children(
     voice_channels::db::delete_template(&get_db_handle(ctx).await, guild_id, parent.id)
         .await
         .wrap_err_with(|| eyre!("Failed to delete template!"))?;
+    get_template_cache(ctx)
+        .await
+        .evict_parents(guild_id, [parent.id])
+        .await;
 
     let guild_map = {
         let guild_channels_map = get_value::<GuildChannels>(&ctx.data).await;
@@ -145,6 +198,7 @@ async fn on_channel_delete(
     let guild_id = channel.guild_id;
     let Some((parent, children)) = voice_channels::db::get_all_children_of_parent(
         &get_db_handle(ctx).await,
+        &*get_template_cache(ctx).await,
         guild_id,
         &[channel.id.get() as i64],
     )
@@ -174,11 +228,17 @@ async fn on_channel_delete(
             .ok_or_else(|| eyre!("Child was not in map!"))?
             .clone();
         channel_set.remove(&child).drop();
+        drop(guild_map_lock);
+        cancel_child_deletion(ctx, channel.id).await.drop();
         let db_handle = get_db_handle(ctx).await;
-        voice_channels::db::update_next_child_number(&db_handle, parent.id, child.id)
+        voice_channels::db::delete_child(&db_handle, channel.guild_id, parent.id, channel.id)
             .await
-            .wrap_err_with(|| eyre!("Failed to update next_channel_number!"))?;
-        voice_channels::db::delete_child(&db_handle, channel.guild_id, parent.id, channel.id).await
+            .wrap_err_with(|| eyre!("Failed to delete child from database!"))?;
+        voice_channels::db::release_child_number(&db_handle, guild_id, &parent)
+            .await
+            .wrap_err_with(|| eyre!("Failed to release child number!"))?;
+        // Renumbered siblings get their new names, the others their new total.
+        update_numbering_scope(ctx, guild_id, &parent).await
     }
 }
 
@@ -217,13 +277,18 @@ pub(crate) async fn on_ready(
     lock.insert::<ClientID>(*LazyLock::force(&CLIENT_ID));
     lock.insert::<GuildChannels>(Arc::new(RwLock::new(HashMap::default())));
     lock.insert::<VoiceStates>(Arc::new(RwLock::new(HashMap::default())));
+    // Deletions scheduled before a reconnect are still pending, so their abort
+    // handles must be kept.
+    if !lock.contains_key::<PendingDeletions>() {
+        lock.insert::<PendingDeletions>(Arc::new(RwLock::new(HashMap::default())));
+    }
+    if !lock.contains_key::<ParsedTemplates>() {
+        lock.insert::<ParsedTemplates>(Arc::default());
+    }
 
     let activity = Some(ActivityData::watching("you sleep"));
     ctx.shard.set_presence(activity, OnlineStatus::Online);
     let connection = lock.get::<DBConnection>().unwrap().clone();
-    voice_channels::db::init_next_child_number(&connection)
-        .await
-        .wrap_err_with(|| eyre!("initializing next child numbers failed!"))?;
 
     debug!("Finished initializing all guilds!");
     info!("Proceeding to remove all inactive guilds");
@@ -238,11 +303,29 @@ pub(crate) async fn on_ready(
     )
     .await
     .wrap_err_with(|| eyre!("Cleaning inactive guilds from database failed!"))?;
+    lock.get::<ParsedTemplates>()
+        .unwrap()
+        .retain_guilds(
+            &ready
+                .guilds
+                .iter()
+                .map(|guild| guild.id)
+                .collect::<Vec<_>>(),
+        )
+        .await;
+    drop(lock);
+
+    if !TIME_REFRESH_STARTED.swap(true, Ordering::Relaxed) {
+        tokio::spawn(
+            refresh_time_dependent_children(ctx.clone())
+                .instrument(info_span!("Time refresh span")),
+        )
+        .drop();
+    }
     info!("Finished running ready!");
     Ok(())
 }
 
-#[allow(clippy::too_many_lines)]
 async fn on_voice_state_update(
     ctx: &SerenityContext,
     old: Option<VoiceState>,
@@ -255,6 +338,10 @@ async fn on_voice_state_update(
 
     info!("Done parsing voice state event!");
 
+    record_voice_state(ctx, parsed_event.new_state())
+        .await
+        .wrap_err_with(|| eyre!("Recording voice state failed!"))?;
+
     let (guild_id, joined_channel_id, left_channel_id) = match &parsed_event {
         | ParsedVoiceStateEvent::Joined {
             joined_channel_id,
@@ -276,143 +363,892 @@ async fn on_voice_state_update(
 
     info!("Parsed event: {:#?}", parsed_event);
 
-    let channels_arr = [left_channel_id, joined_channel_id]
-        .map(|v| v.unwrap_or(ChannelId::new(u64::MAX)).get() as i64);
+    if let Some(left_channel_id) = left_channel_id {
+        on_voice_channel_left(ctx, guild_id, left_channel_id, parsed_event.member())
+            .await
+            .wrap_err_with(|| eyre!("Handling member leaving channel {left_channel_id} failed!"))?;
+    }
+    if let Some(joined_channel_id) = joined_channel_id {
+        on_voice_channel_joined(ctx, guild_id, joined_channel_id, parsed_event.member())
+            .await
+            .wrap_err_with(|| {
+                eyre!("Handling member joining channel {joined_channel_id} failed!")
+            })?;
+    }
 
-    let channels: &[i64] = match (left_channel_id, joined_channel_id) {
-        | (Some(_), Some(_)) => &channels_arr,
-        | (Some(_), None) => &channels_arr[..1],
-        | (None, Some(_)) => &channels_arr[1..],
-        | (None, None) => &[],
-    };
+    Ok(())
+}
 
-    let (parent, children) = voice_channels::db::get_all_children_of_parent(
+/// Builds the settings of a new child, copying those the parent passes on.
+fn new_child_map(parent: &Parent, parent_channel: &GuildChannel) -> Result<Map<String, Value>> {
+    let mut map = Map::new();
+
+    map.insert("type".into(), Value::Number(Number::from(2)))
+        .drop();
+    if let Some(grandparent_id) = parent_channel.parent_id {
+        map.insert("parent_id".into(), grandparent_id.get().to_string().into())
+            .drop();
+    }
+    map.insert("name".into(), "Child".into()).drop();
+    if let Some(cap) = parent.capacity {
+        map.insert("user_limit".into(), Value::Number(Number::from(cap)))
+            .drop();
+    }
+    if let Some(bitrate) = parent_channel
+        .bitrate
+        .filter(|_| parent.inherits(ChildSetting::Bitrate))
+    {
+        map.insert("bitrate".into(), Value::Number(Number::from(bitrate)))
+            .drop();
+    }
+    if let Some(rtc_region) = parent_channel
+        .rtc_region
+        .as_ref()
+        .filter(|_| parent.inherits(ChildSetting::RtcRegion))
+    {
+        map.insert("rtc_region".into(), rtc_region.clone().into())
+            .drop();
+    }
+    if let Some(video_quality_mode) = parent_channel
+        .video_quality_mode
+        .filter(|_| parent.inherits(ChildSetting::VideoQuality))
+    {
+        map.insert(
+            "video_quality_mode".into(),
+            serde_json::to_value(video_quality_mode)
+                .wrap_err_with(|| eyre!("Serializing video quality mode failed!"))?,
+        )
+        .drop();
+    }
+    if parent.inherits(ChildSetting::Nsfw) {
+        map.insert("nsfw".into(), parent_channel.nsfw.into()).drop();
+    }
+    // Children are only as accessible as the parent that spawned them.
+    if parent.inherits(ChildSetting::Permissions) {
+        map.insert(
+            "permission_overwrites".into(),
+            serde_json::to_value(&parent_channel.permission_overwrites)
+                .wrap_err_with(|| eyre!("Serializing permission overwrites failed!"))?,
+        )
+        .drop();
+    }
+    Ok(map)
+}
+
+/// Spawns a new child when a member joins a parent, or re-renders the child a
+/// member joined.
+#[allow(clippy::too_many_lines)]
+async fn on_voice_channel_joined(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    member: &Member,
+) -> Result<()> {
+    let Some((parent, children)) = voice_channels::db::get_all_children_of_parent(
         &get_db_handle(ctx).await,
+        &*get_template_cache(ctx).await,
         guild_id,
-        channels,
+        &[channel_id.get() as i64],
     )
     .await
     .wrap_err_with(|| eyre!("Retrieving voice channels failed!"))?
-    .ok_or_else(|| eyre!("No parent found!"))?;
+    else {
+        return Ok(());
+    };
     info!("Parent: {:?}, children: {:?}", parent, children);
-    if Some(parent.id) == joined_channel_id {
-        let parent_channel = ctx.cache.guild_channel(guild_id, parent.id)?;
-        let mut map = Map::new();
-
-        map.insert("type".into(), Value::Number(Number::from(2)))
-            .drop();
-        if let Some(grandparent_id) = parent_channel.parent_id {
-            map.insert("parent_id".into(), grandparent_id.get().to_string().into())
-                .drop();
-        }
-        map.insert("name".into(), "Child".into()).drop();
-        if let Some(cap) = parent.capacity {
-            map.insert("user_limit".into(), Value::Number(Number::from(cap)))
-                .drop();
+    if parent.id != channel_id {
+        if let Some(child) = children.get(&Child {
+            id: channel_id,
+            ..Default::default()
+        }) {
+            if cancel_child_deletion(ctx, child.id).await {
+                info!("Member rejoined child {} in time, keeping it", child.id);
+                voice_channels::db::mark_child_occupied(&get_db_handle(ctx).await, child.id)
+                    .await
+                    .wrap_err_with(|| eyre!("Failed to mark child as occupied!"))?;
+            }
+            if child.owner_id == Some(member.user.id) {
+                voice_channels::db::set_owner_left(&get_db_handle(ctx).await, child.id, false)
+                    .await
+                    .wrap_err_with(|| eyre!("Failed to record owner returning!"))?;
+            }
+            update_child(ctx, guild_id, &parent, child).await?;
         }
-        let mut new = ctx
-            .http
-            .create_channel(guild_id, &map, Some("Creating new child channel!"))
-            .await
-            .wrap_err_with(|| {
-                eyre!(
-                    "Failed at creating new child for channel {}",
-                    parent_channel.id.get()
-                )
-            })?;
-        let total_children_number = voice_channels::db::register_child(
-            &get_db_handle(ctx).await,
-            guild_id,
-            parent.id,
-            new.id,
-        )
+        return Ok(());
+    }
+    // Checked again when the child is registered, since a concurrent join may
+    // fill the parent in between.
+    if is_at_max_children(children.len() as u64, parent.max_children) {
+        return on_parent_full(ctx, guild_id, &parent, &children, member).await;
+    }
+
+    let template = &parent.templates.name;
+    let render_state = RenderState::new(template);
+    let parent_channel = ctx.cache.guild_channel(guild_id, parent.id)?;
+    let map = new_child_map(&parent, &parent_channel)?;
+    let mut new = ctx
+        .http
+        .create_channel(guild_id, &map, Some("Creating new child channel!"))
         .await
         .wrap_err_with(|| {
-            eyre!("Registering child channel in database for server with id {guild_id} failed!")
+            eyre!(
+                "Failed at creating new child for channel {}",
+                parent_channel.id.get()
+            )
         })?;
-        let map = {
-            let guild_channels_map = get_value::<GuildChannels>(&ctx.data).await;
-            let lock = guild_channels_map.read().await;
-            lock.get(&guild_id).unwrap().clone()
-        };
+    let Some((child_number, pool_entry)) = voice_channels::db::register_child(
+        &get_db_handle(ctx).await,
+        guild_id,
+        parent.id,
+        new.id,
+        member.user.id,
+        template.pool_name(),
+        &render_state,
+    )
+    .await
+    .wrap_err_with(|| {
+        eyre!("Registering child channel in database for server with id {guild_id} failed!")
+    })?
+    else {
+        new.delete(ctx)
+            .await
+            .wrap_err_with(|| eyre!("Failed to delete child {} of full parent!", new.id))?
+            .drop();
+        return on_parent_full(ctx, guild_id, &parent, &children, member).await;
+    };
+    let map = {
+        let guild_channels_map = get_value::<GuildChannels>(&ctx.data).await;
+        let lock = guild_channels_map.read().await;
+        lock.get(&guild_id).unwrap().clone()
+    };
 
-        let mut map_lock = map.write().await;
+    let mut map_lock = map.write().await;
 
-        map_lock
-            .entry(parent.clone())
-            .or_default()
-            .insert(Child {
-                id: new.id,
-                number: total_children_number,
-                total_children_number,
-                template: parent.template.clone(),
-            })
-            .drop();
-        drop(map_lock);
-        drop(map);
-        voice_channels::updater::update_channel(UpdaterContext {
-            template: &parse_template(&parent.template)
-                .wrap_err_with(|| eyre!("Parsing template received from database failed!"))?,
-            context: SerenityContextWrapper(ctx),
-            channel_number: total_children_number,
+    // The new child isn't among the children loaded above.
+    let total_children_number = children.len() as u64 + 1;
+    map_lock
+        .entry(parent.clone())
+        .or_default()
+        .insert(Child {
+            id: new.id,
+            number: child_number,
             total_children_number,
-            channel: &mut new,
+            templates: Arc::clone(&parent.templates),
+            owner_id: Some(member.user.id),
+            pool_name: template.pool_name().map(str::to_owned),
+            pool_entry: pool_entry.clone(),
+            render_state: render_state.clone(),
+            overrides: ChannelOverrides::default(),
         })
+        .drop();
+    drop(map_lock);
+    drop(map);
+    let update = render_channel(
+        &parent.templates,
+        &ChannelOverrides::default(),
+        RenderContext {
+            channel_number: child_number,
+            total_children_number,
+            owner: Some(member.into()),
+            game: current_game(&ctx.cache, guild_id, &[member.user.id]),
+            // The member is about to be moved into the new channel.
+            users: 1,
+            capacity: parent.capacity,
+            now: Utc::now(),
+            time_zone: time_zone_for(ctx, guild_id, &parent.templates).await?,
+            locale: locale_for(ctx, guild_id, &parent.templates).await?,
+            variables: variables_for(ctx, guild_id, &parent.templates).await?,
+            pool_entry,
+            state: render_state,
+        },
+        max_bitrate_for(ctx, guild_id),
+    );
+    // The member must be moved regardless, so a broken template only leaves
+    // the child with its placeholder name.
+    match update {
+        | Ok(update) => update_channel(ctx, &mut new, &update, &parent_channel.name)
+            .await
+            .wrap_err_with(|| eyre!("Updating channel failed!"))?,
+        | Err(err) => error!("Rendering channel {} failed: {err}", new.id),
+    }
+
+    member
+        .move_to_voice_channel(&ctx.http, new.id)
         .await
-        .wrap_err_with(|| eyre!("Updating channel failed!"))?;
+        .wrap_err_with(|| eyre!("Moving member to new channel failed!"))?
+        .drop();
 
-        parsed_event
-            .member()
-            .move_to_voice_channel(&ctx.http, new.id)
+    // The siblings are reloaded so that they see the new total.
+    update_children(ctx, guild_id, &parent).await
+}
+
+/// Moves a member who joined a parent that already has its maximum number of
+/// children into the least full child with room to spare, or tells them that
+/// the lobby is full if there is none.
+async fn on_parent_full(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    parent: &Parent,
+    children: &Children,
+    member: &Member,
+) -> Result<()> {
+    let mut least_full = None;
+    for child in children {
+        let users = voice_channel_members(ctx, guild_id, child.id)
             .await
-            .wrap_err_with(|| eyre!("Moving member to new channel failed!"))?
-            .drop();
-    } else if let Some(child) = children.get(&Child {
-        id: left_channel_id.unwrap_or(ChannelId::new(u64::MAX)),
-        ..Default::default()
-    }) {
-        let channel = ctx.cache.guild_channel(guild_id, child.id)?;
-        let users_connected_number = channel
-            .members(&ctx.cache)
-            .wrap_err_with(|| eyre!("Could not retrieve channel members from cache!"))?
+            .wrap_err_with(|| eyre!("Retrieving members of channel {} failed!", child.id))?
             .len() as u64;
-        if users_connected_number == 0 {
-            ctx.http
-                .delete_channel(child.id, Some("Deleting empty child channel!"))
-                .await
-                .wrap_err_with(|| eyre!("Failed to delete channel!"))?
-                .drop();
-            let db_handle = get_db_handle(ctx).await;
-            voice_channels::db::update_next_child_number(&db_handle, parent.id, child.id)
-                .await
-                .wrap_err_with(|| eyre!("Failed to update next_channel_number!"))?;
-            voice_channels::db::delete_child(&db_handle, guild_id, parent.id, child.id)
-                .await
-                .wrap_err_with(|| eyre!("Failed to delete child from database!"))?;
+        let user_limit = ctx.cache.guild_channel(guild_id, child.id)?.user_limit;
+        let has_room = user_limit.is_none_or(|limit| limit == 0 || users < u64::from(limit));
+        if has_room && least_full.is_none_or(|(_, fewest_users)| users < fewest_users) {
+            least_full = Some((child.id, users));
         }
     }
 
-    for Child {
-        id: child_id,
-        number: child_number,
-        total_children_number,
-        template,
-    } in children
+    if let Some((child_id, _)) = least_full {
+        info!(
+            "Parent {} is full, moving member into child {child_id}",
+            parent.id
+        );
+        return member
+            .move_to_voice_channel(&ctx.http, child_id)
+            .await
+            .wrap_err_with(|| eyre!("Moving member to existing child failed!"))
+            .map(|_| ());
+    }
+
+    let parent_name = ctx.cache.guild_channel(guild_id, parent.id)?.name;
+    let locale = get_locale(&get_db_handle(ctx).await, guild_id)
+        .await
+        .wrap_err_with(|| eyre!("Retrieving locale of guild {guild_id} failed!"))?;
+    let message = Reply::LobbyFull {
+        parent: &parent_name,
+    }
+    .localize(locale);
+    // Members may have direct messages turned off, which is no reason to fail.
+    if let Err(err) = member
+        .user
+        .direct_message(&ctx.http, CreateMessage::new().content(message))
+        .await
     {
-        debug!("Updating child channel with id {child_id} and number {child_number}",);
-        let mut channel = ctx.cache.guild_channel(guild_id, child_id)?;
-        voice_channels::updater::update_channel(UpdaterContext {
-            template: &parse_template(&template)
-                .wrap_err_with(|| eyre!("Parsing template received from database failed!"))?,
-            context: SerenityContextWrapper(ctx),
-            channel_number: child_number,
-            total_children_number,
-            channel: &mut channel,
+        warn!(
+            "Telling {} that parent {} is full failed: {err}",
+            member.user.id, parent.id
+        );
+    }
+    Ok(())
+}
+
+/// Deletes the child a member left if it is now empty, or re-renders it
+/// otherwise. Parents with a grace period keep empty children around for a
+/// while in case someone rejoins.
+async fn on_voice_channel_left(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    member: &Member,
+) -> Result<()> {
+    let db_handle = get_db_handle(ctx).await;
+    let Some((parent, children)) = voice_channels::db::get_all_children_of_parent(
+        &db_handle,
+        &*get_template_cache(ctx).await,
+        guild_id,
+        &[channel_id.get() as i64],
+    )
+    .await
+    .wrap_err_with(|| eyre!("Retrieving voice channels failed!"))?
+    else {
+        return Ok(());
+    };
+    let Some(mut child) = children
+        .get(&Child {
+            id: channel_id,
+            ..Default::default()
         })
+        .cloned()
+    else {
+        return Ok(());
+    };
+    let users_connected_number = voice_channel_members(ctx, guild_id, child.id)
         .await
-        .wrap_err_with(|| eyre!("Updating channel failed!"))?;
+        .wrap_err_with(|| eyre!("Retrieving members of channel {} failed!", child.id))?
+        .len() as u64;
+    if child.owner_id == Some(member.user.id) {
+        child.owner_id = Some(on_owner_left(ctx, guild_id, &parent, &child, member.user.id).await?);
+    }
+    if users_connected_number > 0 {
+        return update_child(ctx, guild_id, &parent, &child).await;
+    }
+    if parent.grace_period.is_zero() {
+        return delete_empty_child(ctx, guild_id, &parent, child.id).await;
     }
 
+    let delay = voice_channels::db::mark_child_empty(&db_handle, child.id)
+        .await
+        .wrap_err_with(|| eyre!("Failed to mark child as empty!"))?;
+    info!("Child {} is empty, deleting it in {delay:?}", child.id);
+    schedule_child_deletion(ctx, guild_id, child.id, delay).await;
+    // The child now has no users, which its name may show.
+    update_child(ctx, guild_id, &parent, &child).await
+}
+
+/// Passes ownership of a child to the member who has been in it the longest if
+/// its parent transfers ownership automatically. Otherwise, or if nobody is
+/// left, records when the owner left so that the child can be claimed later.
+/// Returns the owner of the child afterwards.
+async fn on_owner_left(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    parent: &Parent,
+    child: &Child,
+    owner_id: UserId,
+) -> Result<UserId> {
+    let db_handle = get_db_handle(ctx).await;
+    if parent.ownership_transfer == OwnershipTransfer::Automatic {
+        if let Some(new_owner_id) = longest_present_member(ctx, guild_id, child.id).await? {
+            voice_channels::db::set_child_owner(&db_handle, child.id, new_owner_id)
+                .await
+                .wrap_err_with(|| eyre!("Failed to transfer ownership!"))?;
+            info!(
+                "Transferred ownership of child {} from {owner_id} to {new_owner_id}",
+                child.id
+            );
+            return Ok(new_owner_id);
+        }
+    }
+    voice_channels::db::set_owner_left(&db_handle, child.id, true)
+        .await
+        .wrap_err_with(|| eyre!("Failed to record owner leaving!"))?;
+    Ok(owner_id)
+}
+
+/// Deletes an empty child and renumbers the children sharing numbers with it.
+async fn delete_empty_child(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    parent: &Parent,
+    child_id: ChannelId,
+) -> Result<()> {
+    ctx.http
+        .delete_channel(child_id, Some("Deleting empty child channel!"))
+        .await
+        .wrap_err_with(|| eyre!("Failed to delete channel!"))?
+        .drop();
+    let db_handle = get_db_handle(ctx).await;
+    voice_channels::db::delete_child(&db_handle, guild_id, parent.id, child_id)
+        .await
+        .wrap_err_with(|| eyre!("Failed to delete child from database!"))?;
+    voice_channels::db::release_child_number(&db_handle, guild_id, parent)
+        .await
+        .wrap_err_with(|| eyre!("Failed to release child number!"))?;
+
+    update_numbering_scope(ctx, guild_id, parent).await
+}
+
+/// Deletes a child once `delay` has passed, unless the deletion is cancelled
+/// before then. Replaces any deletion already pending for the child.
+async fn schedule_child_deletion(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    child_id: ChannelId,
+    delay: Duration,
+) {
+    let pending_deletions = get_value::<PendingDeletions>(&ctx.data).await;
+    // The lock is held until the task is registered, so that it can't finish
+    // before it can be found.
+    let mut lock = pending_deletions.write().await;
+    let task = tokio::spawn(
+        delete_child_after(ctx.clone(), guild_id, child_id, delay)
+            .instrument(info_span!("Pending deletion span")),
+    );
+    if let Some(previous) = lock.insert(child_id, task.abort_handle()) {
+        previous.abort();
+    }
+}
+
+/// Cancels the pending deletion of a child, returning whether there was one.
+async fn cancel_child_deletion(ctx: &SerenityContext, child_id: ChannelId) -> bool {
+    let pending_deletions = get_value::<PendingDeletions>(&ctx.data).await;
+    let Some(task) = pending_deletions.write().await.remove(&child_id) else {
+        return false;
+    };
+    task.abort();
+    true
+}
+
+/// Waits out the grace period of a child and then deletes it.
+async fn delete_child_after(
+    ctx: SerenityContext,
+    guild_id: GuildId,
+    child_id: ChannelId,
+    delay: Duration,
+) {
+    tokio::time::sleep(delay).await;
+    {
+        let pending_deletions = get_value::<PendingDeletions>(&ctx.data).await;
+        pending_deletions.write().await.remove(&child_id).drop();
+    }
+    // The deletion can no longer be cancelled past this point, so members who
+    // joined in the meantime have to be looked for once more.
+    if let Err(err) = delete_child_if_empty(&ctx, guild_id, child_id).await {
+        error!("Deleting child {child_id} after its grace period failed: {err:?}");
+    }
+}
+
+/// Deletes a child if it still exists and nobody is connected to it.
+async fn delete_child_if_empty(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    child_id: ChannelId,
+) -> Result<()> {
+    let Some((parent, children)) = voice_channels::db::get_all_children_of_parent(
+        &get_db_handle(ctx).await,
+        &*get_template_cache(ctx).await,
+        guild_id,
+        &[child_id.get() as i64],
+    )
+    .await
+    .wrap_err_with(|| eyre!("Retrieving voice channels failed!"))?
+    else {
+        return Ok(());
+    };
+    if !children.iter().any(|child| child.id == child_id) {
+        return Ok(());
+    }
+    let members = voice_channel_members(ctx, guild_id, child_id)
+        .await
+        .wrap_err_with(|| eyre!("Retrieving members of channel {child_id} failed!"))?;
+    if !members.is_empty() {
+        return voice_channels::db::mark_child_occupied(&get_db_handle(ctx).await, child_id)
+            .await
+            .wrap_err_with(|| eyre!("Failed to mark child as occupied!"));
+    }
+    delete_empty_child(ctx, guild_id, &parent, child_id).await
+}
+
+/// Schedules the deletion of children that were left empty while the bot was
+/// away, or whose grace period was cut short by a restart. Children that were
+/// already waiting keep the time they became empty.
+async fn reschedule_empty_children(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    empty_child_ids: Vec<ChannelId>,
+) {
+    let db_handle = get_db_handle(ctx).await;
+    for child_id in empty_child_ids {
+        match voice_channels::db::mark_child_empty(&db_handle, child_id).await {
+            | Ok(delay) => schedule_child_deletion(ctx, guild_id, child_id, delay).await,
+            | Err(err) => error!("Rescheduling deletion of child {child_id} failed: {err:?}"),
+        }
+    }
+}
+
+/// Re-renders the names of the children of a parent and of every other parent
+/// in its numbering group. A parent whose children can't be updated doesn't
+/// keep the other parents from being updated.
+pub(crate) async fn update_numbering_scope(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    parent: &Parent,
+) -> Result<()> {
+    let parent_ids =
+        voice_channels::db::get_numbering_scope(&get_db_handle(ctx).await, guild_id, parent)
+            .await
+            .wrap_err_with(|| eyre!("Retrieving numbering group failed!"))?;
+    for parent_id in parent_ids {
+        if let Err(err) = update_children(ctx, guild_id, &Parent::from(parent_id)).await {
+            error!("Updating children of parent {parent_id} failed: {err:?}");
+        }
+    }
+    Ok(())
+}
+
+/// Re-renders the names of all living children of a parent. A child that can't
+/// be updated, e.g. because it was deleted by hand, doesn't keep the others
+/// from being updated.
+pub(crate) async fn update_children(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    parent: &Parent,
+) -> Result<()> {
+    let Some((parent, children)) = voice_channels::db::get_all_children_of_parent(
+        &get_db_handle(ctx).await,
+        &*get_template_cache(ctx).await,
+        guild_id,
+        &[parent.id.get() as i64],
+    )
+    .await
+    .wrap_err_with(|| eyre!("Retrieving voice channels failed!"))?
+    else {
+        return Ok(());
+    };
+    for child in &children {
+        if let Err(err) = update_child(ctx, guild_id, &parent, child).await {
+            error!("Updating child {} failed: {err:?}", child.id);
+        }
+    }
+    Ok(())
+}
+
+/// Clears the status of all living children of a parent, which rendering
+/// leaves untouched once the parent has no status template.
+pub(crate) async fn clear_child_statuses(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    parent: &Parent,
+) -> Result<()> {
+    let Some((_, children)) = voice_channels::db::get_all_children_of_parent(
+        &get_db_handle(ctx).await,
+        &*get_template_cache(ctx).await,
+        guild_id,
+        &[parent.id.get() as i64],
+    )
+    .await
+    .wrap_err_with(|| eyre!("Retrieving voice channels failed!"))?
+    else {
+        return Ok(());
+    };
+    for child in &children {
+        if let Err(err) = set_voice_status(ctx, child.id, "").await {
+            error!("Clearing status of child {} failed: {err:?}", child.id);
+        }
+    }
+    Ok(())
+}
+
+/// Re-renders the name of a single child channel.
+pub(crate) async fn update_child(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    parent: &Parent,
+    child: &Child,
+) -> Result<()> {
+    debug!(
+        "Updating child channel with id {} and number {}",
+        child.id, child.number
+    );
+    let mut channel = ctx.cache.guild_channel(guild_id, child.id)?;
+    let owner = child
+        .owner_id
+        .and_then(|owner_id| ctx.cache.guild_member(guild_id, owner_id));
+    let members = voice_channel_members(ctx, guild_id, child.id)
+        .await
+        .wrap_err_with(|| eyre!("Retrieving members of channel {} failed!", child.id))?;
+    let template = &child.templates.name;
+    let mut render_state = child.render_state.clone();
+    if render_state.reconcile(template) {
+        voice_channels::db::set_render_state(&get_db_handle(ctx).await, child.id, &render_state)
+            .await
+            .wrap_err_with(|| eyre!("Storing render state of channel {} failed!", child.id))?;
+    }
+    let update = render_channel(
+        &child.templates,
+        &child.overrides,
+        RenderContext {
+            channel_number:        child.number,
+            total_children_number: child.total_children_number,
+            owner:                 owner.as_ref().map(OwnerInfo::from),
+            game:                  current_game(&ctx.cache, guild_id, &members),
+            users:                 members.len() as u64,
+            capacity:              parent.capacity,
+            now:                   Utc::now(),
+            time_zone:             time_zone_for(ctx, guild_id, &child.templates).await?,
+            locale:                locale_for(ctx, guild_id, &child.templates).await?,
+            variables:             variables_for(ctx, guild_id, &child.templates).await?,
+            // The entry is only meaningful if the template still uses the
+            // pool it was allocated from.
+            pool_entry:            child
+                .pool_entry
+                .clone()
+                .filter(|_| child.pool_name.as_deref() == template.pool_name()),
+            state:                 render_state,
+        },
+        max_bitrate_for(ctx, guild_id),
+    )
+    .wrap_err_with(|| eyre!("Rendering channel {} failed!", child.id))?;
+    let parent_name = ctx.cache.guild_channel(guild_id, parent.id)?.name;
+    update_channel(ctx, &mut channel, &update, &parent_name)
+        .await
+        .wrap_err_with(|| eyre!("Updating channel failed!"))
+}
+
+/// Returns the highest bitrate in kbps the boost tier of a guild allows.
+fn max_bitrate_for(ctx: &SerenityContext, guild_id: GuildId) -> u32 {
+    max_bitrate(
+        ctx.cache
+            .guild(guild_id)
+            .map(|guild| guild.premium_tier)
+            .unwrap_or_default(),
+    )
+}
+
+/// Returns the time zone of a guild, but only looks it up if the templates
+/// have time directives that need it.
+async fn time_zone_for(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    templates: &ChannelTemplates,
+) -> Result<Tz> {
+    if !templates.any(Template::is_time_dependent) {
+        return Ok(Tz::default());
+    }
+    get_time_zone(&get_db_handle(ctx).await, guild_id)
+        .await
+        .wrap_err_with(|| eyre!("Retrieving time zone of guild {guild_id} failed!"))
+}
+
+/// Returns the locale of a guild, but only looks it up if the templates spell
+/// out numbers.
+async fn locale_for(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    templates: &ChannelTemplates,
+) -> Result<Locale> {
+    if !templates.any(Template::is_locale_dependent) {
+        return Ok(Locale::default());
+    }
+    get_locale(&get_db_handle(ctx).await, guild_id)
+        .await
+        .wrap_err_with(|| eyre!("Retrieving locale of guild {guild_id} failed!"))
+}
+
+/// Returns the variables of a guild, but only looks them up if the templates
+/// reference any.
+async fn variables_for(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    templates: &ChannelTemplates,
+) -> Result<HashMap<String, String>> {
+    if !templates.any(Template::uses_variables) {
+        return Ok(HashMap::default());
+    }
+    voice_channels::db::get_variables(&get_db_handle(ctx).await, guild_id)
+        .await
+        .wrap_err_with(|| eyre!("Retrieving variables of guild {guild_id} failed!"))
+}
+
+/// Re-renders every child in a guild whose name or status template has time
+/// directives.
+pub(crate) async fn update_time_dependent_children(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+) -> Result<()> {
+    update_matching_children(ctx, guild_id, Template::is_time_dependent).await
+}
+
+/// Re-renders every child in a guild whose name or status template spells out
+/// numbers.
+pub(crate) async fn update_locale_dependent_children(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+) -> Result<()> {
+    update_matching_children(ctx, guild_id, Template::is_locale_dependent).await
+}
+
+/// Re-renders every child in a guild whose name or status template references
+/// a variable.
+pub(crate) async fn update_children_using_variable(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    name: &str,
+) -> Result<()> {
+    update_matching_children(ctx, guild_id, |template| template.uses_variable(name)).await
+}
+
+/// Re-renders every child in a guild whose name or status template matches the
+/// predicate.
+async fn update_matching_children(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    predicate: impl Fn(&Template) -> bool,
+) -> Result<()> {
+    let all_channels = voice_channels::db::get_all_channels_in_guild(
+        &get_db_handle(ctx).await,
+        &*get_template_cache(ctx).await,
+        guild_id,
+    )
+    .await
+    .wrap_err_with(|| eyre!("Retrieving voice channels failed in guild `{guild_id}`!"))?;
+    for (parent, children) in &all_channels {
+        if !parent.templates.any(&predicate) {
+            continue;
+        }
+        for child in children {
+            if let Err(err) = update_child(ctx, guild_id, parent, child).await {
+                error!("Updating child {} failed: {err:?}", child.id);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Periodically re-renders children with time directives, since their names
+/// change without any voice event happening.
+async fn refresh_time_dependent_children(ctx: SerenityContext) {
+    let mut interval = tokio::time::interval(TIME_REFRESH_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await.drop();
+        let guild_ids = {
+            let guild_channels_map = get_value::<GuildChannels>(&ctx.data).await;
+            let lock = guild_channels_map.read().await;
+            lock.keys().copied().collect::<Vec<_>>()
+        };
+        trace!(
+            "Refreshing time dependent children in {} guilds",
+            guild_ids.len()
+        );
+        for guild_id in guild_ids {
+            if let Err(err) = update_time_dependent_children(&ctx, guild_id).await {
+                error!("Refreshing time dependent children in guild {guild_id} failed: {err:?}");
+            }
+        }
+    }
+}
+
+/// Keeps the tracked voice states of a guild up to date.
+async fn record_voice_state(ctx: &SerenityContext, state: &VoiceState) -> Result<()> {
+    let guild_id = state
+        .guild_id
+        .ok_or_else(|| eyre!("No guild id provided!"))?;
+    let voice_states = {
+        let voice_states_map = get_value::<VoiceStates>(&ctx.data).await;
+        let mut lock = voice_states_map.write().await;
+        // Guilds whose voice states haven't been loaded yet start out empty.
+        lock.entry(guild_id).or_default().clone()
+    };
+    let mut lock = voice_states.write().await;
+    if state.channel_id.is_some() {
+        // Mutes and the like don't count as joining again.
+        let joined_at = lock
+            .get(&state.user_id)
+            .filter(|tracked| tracked.state.channel_id == state.channel_id)
+            .map_or_else(Instant::now, |tracked| tracked.joined_at);
+        lock.insert(
+            state.user_id,
+            TrackedVoiceState {
+                state: state.clone(),
+                joined_at,
+            },
+        )
+        .drop();
+    } else {
+        lock.remove(&state.user_id).drop();
+    }
+    Ok(())
+}
+
+/// Returns the ids of all members connected to a voice channel according to
+/// the tracked voice states.
+async fn voice_channel_members(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Result<Vec<UserId>> {
+    let voice_states = {
+        let voice_states_map = get_value::<VoiceStates>(&ctx.data).await;
+        let lock = voice_states_map.read().await;
+        lock.get(&guild_id)
+            .ok_or_else(|| eyre!("Guild {guild_id} has no tracked voice states!"))?
+            .clone()
+    };
+    let lock = voice_states.read().await;
+    Ok(lock
+        .iter()
+        .filter(|(_, tracked)| tracked.state.channel_id == Some(channel_id))
+        .map(|(user_id, _)| *user_id)
+        .collect())
+}
+
+/// Returns the member, other than bots, who has been connected to a voice
+/// channel the longest according to the tracked voice states.
+async fn longest_present_member(
+    ctx: &SerenityContext,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Result<Option<UserId>> {
+    let voice_states = {
+        let voice_states_map = get_value::<VoiceStates>(&ctx.data).await;
+        let lock = voice_states_map.read().await;
+        lock.get(&guild_id)
+            .ok_or_else(|| eyre!("Guild {guild_id} has no tracked voice states!"))?
+            .clone()
+    };
+    let lock = voice_states.read().await;
+    Ok(lock
+        .iter()
+        .filter(|(_, tracked)| tracked.state.channel_id == Some(channel_id))
+        .filter(|(_, tracked)| {
+            tracked
+                .state
+                .member
+                .as_ref()
+                .is_none_or(|member| !member.user.bot)
+        })
+        .min_by_key(|(_, tracked)| tracked.joined_at)
+        .map(|(user_id, _)| *user_id))
+}
+
+/// Returns the game most of the given members are playing according to their
+/// cached presences.
+fn current_game(cache: &Cache, guild_id: GuildId, members: &[UserId]) -> Option<String> {
+    let guild = cache.guild(guild_id)?;
+    most_common_game(
+        members
+            .iter()
+            .filter_map(|user_id| guild.presences.get(user_id))
+            .map(|presence| {
+                presence
+                    .activities
+                    .iter()
+                    .filter(|activity| activity.kind == ActivityType::Playing)
+                    .map(|activity| activity.name.as_str())
+            }),
+    )
+    .map(str::to_owned)
+}
+
+async fn on_presence_update(ctx: &SerenityContext, presence: &Presence) -> Result<()> {
+    let Some(guild_id) = presence.guild_id else {
+        return Ok(());
+    };
+    let voice_states = {
+        let voice_states_map = get_value::<VoiceStates>(&ctx.data).await;
+        let lock = voice_states_map.read().await;
+        lock.get(&guild_id).cloned()
+    };
+    let Some(voice_states) = voice_states else {
+        return Ok(());
+    };
+    let channel_id = voice_states
+        .read()
+        .await
+        .get(&presence.user.id)
+        .and_then(|tracked| tracked.state.channel_id);
+    let Some(channel_id) = channel_id else {
+        return Ok(());
+    };
+    trace!(
+        "Presence of user {} in channel {channel_id} changed",
+        presence.user.id
+    );
+    let Some((parent, children)) = voice_channels::db::get_all_children_of_parent(
+        &get_db_handle(ctx).await,
+        &*get_template_cache(ctx).await,
+        guild_id,
+        &[channel_id.get() as i64],
+    )
+    .await
+    .wrap_err_with(|| eyre!("Retrieving voice channels failed!"))?
+    else {
+        return Ok(());
+    };
+    if let Some(child) = children.get(&Child {
+        id: channel_id,
+        ..Default::default()
+    }) {
+        update_child(ctx, guild_id, &parent, child).await?;
+    }
     Ok(())
 }
 
@@ -422,6 +1258,7 @@ async fn on_message_created(_ctx: &SerenityContext, msg: &Message) -> Result<()>
     Ok(())
 }
 
+#[allow(clippy::too_many_lines)]
 async fn on_guild_join(ctx: &SerenityContext, guild: &Guild, _is_new: Option<bool>) -> Result<()> {
     info!("Joined guild: {}", guild.name);
     let guild_channels_map = get_value::<GuildChannels>(&ctx.data).await;
@@ -430,9 +1267,13 @@ async fn on_guild_join(ctx: &SerenityContext, guild: &Guild, _is_new: Option<boo
 
     let connection = get_db_handle(ctx).await;
 
-    let all_channels = voice_channels::db::get_all_channels_in_guild(&connection, guild_id)
-        .await
-        .wrap_err_with(|| eyre!("Retrieving voice channels failed in guild `{guild_id}`!"))?;
+    let all_channels = voice_channels::db::get_all_channels_in_guild(
+        &connection,
+        &*get_template_cache(ctx).await,
+        guild_id,
+    )
+    .await
+    .wrap_err_with(|| eyre!("Retrieving voice channels failed in guild `{guild_id}`!"))?;
     debug!("All channels for guild: {guild_id}: {all_channels:?}");
 
     let mut deleted_child_ids = Vec::new();
@@ -456,6 +1297,24 @@ async fn on_guild_join(ctx: &SerenityContext, guild: &Guild, _is_new: Option<boo
         debug!("Deleted children: {deleted_child_ids:?}");
     }
 
+    let live_parents = all_channels
+        .keys()
+        .filter(|parent| guild.channels.contains_key(&parent.id))
+        .cloned()
+        .collect::<Vec<_>>();
+    let (empty_child_ids, occupied_child_ids): (Vec<_>, Vec<_>) = all_channels
+        .iter()
+        .filter(|(parent, _)| guild.channels.contains_key(&parent.id))
+        .flat_map(|(_, children)| children)
+        .filter(|child| guild.channels.contains_key(&child.id))
+        .map(|child| child.id)
+        .partition(|&child_id| {
+            guild
+                .voice_states
+                .values()
+                .all(|state| state.channel_id != Some(child_id))
+        });
+
     voice_channels::db::remove_dead_channels(
         &get_db_handle(ctx).await,
         &deleted_parent_ids,
@@ -463,6 +1322,26 @@ async fn on_guild_join(ctx: &SerenityContext, guild: &Guild, _is_new: Option<boo
     )
     .await
     .wrap_err_with(|| eyre!("Deleting children failed!"))?;
+    get_template_cache(ctx)
+        .await
+        .evict_parents(
+            guild_id,
+            deleted_parent_ids
+                .iter()
+                .map(|&parent_id| ChannelId::new(parent_id as u64)),
+        )
+        .await;
+    // Children that were empty at shutdown but were joined since mustn't keep
+    // the time they became empty, or they would skip their next grace period.
+    voice_channels::db::mark_children_occupied(
+        &get_db_handle(ctx).await,
+        &occupied_child_ids
+            .iter()
+            .map(|child_id| child_id.get() as i64)
+            .collect::<Vec<_>>(),
+    )
+    .await
+    .wrap_err_with(|| eyre!("Marking children as occupied failed!"))?;
     guild_channels_lock
         .insert(guild_id, Arc::new(RwLock::new(all_channels)))
         .drop();
@@ -471,7 +1350,18 @@ async fn on_guild_join(ctx: &SerenityContext, guild: &Guild, _is_new: Option<boo
     info!("Updating voice states for guild: {}", guild_id);
     let mut voice_states = HashMap::default();
 
-    voice_states.extend(guild.voice_states.iter().map(|(k, v)| (*k, v.clone())));
+    // When members joined before the bot started is unknown, so they all count
+    // as having joined now.
+    let now = Instant::now();
+    voice_states.extend(guild.voice_states.iter().map(|(k, v)| {
+        (
+            *k,
+            TrackedVoiceState {
+                state:     v.clone(),
+                joined_at: now,
+            },
+        )
+    }));
     info!("Finished updating voice states for guild: {}", guild_id);
     debug!("Voice states: {voice_states:?}");
 
@@ -480,6 +1370,19 @@ async fn on_guild_join(ctx: &SerenityContext, guild: &Guild, _is_new: Option<boo
     voice_states_lock
         .insert(guild_id, Arc::new(RwLock::new(voice_states)))
         .drop();
+    drop(voice_states_lock);
+
+    info!("Reconciling child channel names for guild: {}", guild_id);
+    for parent in live_parents {
+        // Names that can't be reconciled now will be fixed by the next voice event.
+        if let Err(err) = update_children(ctx, guild_id, &parent).await {
+            error!(
+                "Reconciling children of parent {} failed: {err:?}",
+                parent.id
+            );
+        }
+    }
+    reschedule_empty_children(ctx, guild_id, empty_child_ids).await;
 
     Ok(())
 }
@@ -509,6 +1412,8 @@ async fn on_guild_leave(
     let mut voice_states_lock = voice_states_map.write().await;
     voice_states_lock.remove(&guild_id).drop();
 
+    get_template_cache(ctx).await.evict_guild(guild_id).await;
+
     Ok(())
 }
 
@@ -547,6 +1452,12 @@ impl ParsedVoiceStateEvent {
             | Self::Changed { member, .. } => member,
         }
     }
+
+    fn new_state(&self) -> &VoiceState {
+        match self {
+            | Self::Joined { new, .. } | Self::Left { new, .. } | Self::Changed { new, .. } => new,
+        }
+    }
 }
 
 fn parse_voice_event(
@@ -643,6 +1554,10 @@ pub(crate) async fn on_event(
                 on_channel_update(ctx, old.as_ref(), new)
                     .instrument(trace_span!("Channel update"))
                     .await,
+            | FullEvent::PresenceUpdate { new_data } =>
+                on_presence_update(ctx, new_data)
+                    .instrument(trace_span!("Presence update"))
+                    .await,
             | FullEvent::ChannelDelete { channel, messages } =>
                 on_channel_delete(ctx, channel, messages.as_ref())
                     .instrument(trace_span!("Channel delete"))
@@ -653,3 +1568,62 @@ pub(crate) async fn on_event(
     .instrument(event_span)
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+    use serenity::all::{
+        PermissionOverwrite,
+        PermissionOverwriteType,
+        Permissions,
+        RoleId,
+        VideoQualityMode,
+    };
+
+    use super::*;
+
+    #[rstest]
+    #[case(&[], json!({
+        "type": 2,
+        "name": "Child",
+        "bitrate": 96_000,
+        "rtc_region": "rotterdam",
+        "video_quality_mode": 2,
+        "nsfw": true,
+        "permission_overwrites": [{ "allow": "0", "deny": "1024", "type": 0, "id": "2" }],
+    }))]
+    #[case(&[ChildSetting::Bitrate, ChildSetting::Nsfw], json!({
+        "type": 2,
+        "name": "Child",
+        "rtc_region": "rotterdam",
+        "video_quality_mode": 2,
+        "permission_overwrites": [{ "allow": "0", "deny": "1024", "type": 0, "id": "2" }],
+    }))]
+    #[case(&[ChildSetting::RtcRegion, ChildSetting::VideoQuality, ChildSetting::Permissions], json!({
+        "type": 2,
+        "name": "Child",
+        "bitrate": 96_000,
+        "nsfw": true,
+    }))]
+    fn test_new_child_map(#[case] uninherited: &[ChildSetting], #[case] expected: Value) {
+        let parent = Parent {
+            uninherited: uninherited.to_vec(),
+            ..Parent::from(ChannelId::new(1))
+        };
+        let mut parent_channel = GuildChannel::default();
+        parent_channel.bitrate = Some(96_000);
+        parent_channel.rtc_region = Some("rotterdam".to_owned());
+        parent_channel.video_quality_mode = Some(VideoQualityMode::Full);
+        parent_channel.nsfw = true;
+        parent_channel.permission_overwrites = vec![PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny:  Permissions::VIEW_CHANNEL,
+            kind:  PermissionOverwriteType::Role(RoleId::new(2)),
+        }];
+
+        let map = new_child_map(&parent, &parent_channel).unwrap();
+        assert_eq!(expected, Value::Object(map));
+    }
+}
@@ -0,0 +1,658 @@
+/// The language a guild's number words and bot replies are in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, poise::ChoiceParameter)]
+pub(crate) enum Locale {
+    #[default]
+    #[name = "English"]
+    English,
+    #[name = "Svenska"]
+    Swedish,
+    #[name = "Deutsch"]
+    German,
+}
+
+impl Locale {
+    /// The code the locale is stored in the database as.
+    pub(crate) fn code(self) -> &'static str {
+        match self {
+            | Self::English => "en",
+            | Self::Swedish => "sv",
+            | Self::German => "de",
+        }
+    }
+
+    /// The locale dates and times are formatted in.
+    pub(crate) fn time_locale(self) -> chrono::Locale {
+        match self {
+            | Self::English => chrono::Locale::en_US,
+            | Self::Swedish => chrono::Locale::sv_SE,
+            | Self::German => chrono::Locale::de_DE,
+        }
+    }
+
+    pub(crate) fn from_code(code: &str) -> Option<Self> {
+        match code {
+            | "en" => Some(Self::English),
+            | "sv" => Some(Self::Swedish),
+            | "de" => Some(Self::German),
+            | _ => None,
+        }
+    }
+}
+
+/// A message the bot sends to members, without the mention of the member it
+/// is addressed to.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Reply<'a> {
+    InvalidTemplate,
+    TemplateChanged {
+        template: &'a str,
+    },
+    ChannelCreated {
+        name:     &'a str,
+        template: &'a str,
+    },
+    PreviewOf {
+        template: &'a str,
+    },
+    EmptyNameWarning {
+        child: u64,
+    },
+    TooLongWarning {
+        child:  u64,
+        length: usize,
+        max:    usize,
+    },
+    CapacityChanged {
+        capacity: u64,
+    },
+    CapacityCleared {
+        channel_id: u64,
+    },
+    StatusTemplateChanged {
+        template: &'a str,
+    },
+    StatusTemplateRemoved,
+    BitrateChanged {
+        expression: &'a str,
+    },
+    BitrateReset,
+    UserLimitChanged {
+        expression: &'a str,
+    },
+    UserLimitReset,
+    NumberingChanged {
+        strategy: &'a str,
+    },
+    NumberingGroupChanged {
+        group: &'a str,
+    },
+    NumberingGroupLeft,
+    FirstNumberChanged {
+        number: u64,
+    },
+    MaxChildrenChanged {
+        max: u64,
+    },
+    MaxChildrenRemoved,
+    GracePeriodChanged {
+        seconds: u64,
+    },
+    GracePeriodRemoved,
+    UnknownTimeZone {
+        time_zone: &'a str,
+    },
+    TimeZoneChanged {
+        time_zone: &'a str,
+    },
+    LocaleChanged,
+    NameEmpty,
+    NameTooLong,
+    NameInvalidCharacters,
+    VariableValueTooLong,
+    VariableValueControlCharacters,
+    VariableSet {
+        name:  &'a str,
+        value: &'a str,
+    },
+    VariableDeleted {
+        name: &'a str,
+    },
+    NoSuchVariable {
+        name: &'a str,
+    },
+    NoVariables,
+    Variables,
+    NamePoolEmpty,
+    NamePoolTooLarge,
+    PoolEntryTooLong,
+    PoolEntryControlCharacters,
+    PoolEntriesNotUnique,
+    NamePoolSet {
+        name:    &'a str,
+        entries: usize,
+    },
+    NamePoolDeleted {
+        name: &'a str,
+    },
+    NoSuchNamePool {
+        name: &'a str,
+    },
+    NoNamePools,
+    NamePools,
+    ListedParent {
+        number:   usize,
+        name:     &'a str,
+        template: &'a str,
+    },
+    ListedChild {
+        number: u64,
+        name:   &'a str,
+    },
+    LobbyFull {
+        parent: &'a str,
+    },
+    NotInChild,
+    NotChannelOwner,
+    ChannelNameInvalid,
+    ChannelRenamed {
+        name: &'a str,
+    },
+    ChannelNameReset,
+    ChannelLimitChanged {
+        limit: u64,
+    },
+    ChannelLimitReset,
+    ChannelBitrateChanged {
+        kbps: u64,
+    },
+    ChannelBitrateReset,
+    MemberKicked {
+        member: &'a str,
+    },
+    MemberNotInChannel {
+        member: &'a str,
+    },
+    CannotKickSelf,
+    OwnershipTransferChanged {
+        transfer: &'a str,
+    },
+    AlreadyOwner,
+    OwnerStillPresent,
+    ClaimTooEarly {
+        seconds: u64,
+    },
+    ChannelClaimed,
+    ChannelLocked,
+    ChannelHidden,
+    ChannelUnlocked,
+    MemberPermitted {
+        member: &'a str,
+    },
+    PermitDeniedByParent {
+        member: &'a str,
+    },
+    MemberRejected {
+        member: &'a str,
+    },
+    CannotRejectSelf,
+    InheritanceChanged {
+        setting: &'a str,
+        inherit: bool,
+    },
+}
+
+impl Reply<'_> {
+    pub(crate) fn localize(&self, locale: Locale) -> String {
+        match locale {
+            | Locale::English => self.english(),
+            | Locale::Swedish => self.swedish(),
+            | Locale::German => self.german(),
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn english(&self) -> String {
+        match *self {
+            | Self::InvalidTemplate => "Invalid template!".to_owned(),
+            | Self::TemplateChanged { template } =>
+                format!("Template successfully changed to `{template}`!"),
+            | Self::ChannelCreated { name, template } => format!(
+                "Channel successfully created with name `{name}` and template `{template}`!"
+            ),
+            | Self::PreviewOf { template } => format!("Preview of `{template}`:"),
+            | Self::EmptyNameWarning { child } =>
+                format!("⚠️ Child {child} would have an empty name, which Discord rejects."),
+            | Self::TooLongWarning { child, length, max } => format!(
+                "⚠️ Child {child} would be {length} characters long and will be truncated to \
+                 {max}."
+            ),
+            | Self::CapacityChanged { capacity } =>
+                format!("Successfully changed capacity to {capacity}!"),
+            | Self::CapacityCleared { channel_id } =>
+                format!("Successfully cleared capacity for channel with ID {channel_id}!"),
+            | Self::StatusTemplateChanged { template } =>
+                format!("Status template successfully changed to `{template}`!"),
+            | Self::StatusTemplateRemoved => "Status template successfully removed!".to_owned(),
+            | Self::BitrateChanged { expression } =>
+                format!("Bitrate successfully changed to `{expression}` kbps!"),
+            | Self::BitrateReset => "Bitrate successfully reset!".to_owned(),
+            | Self::UserLimitChanged { expression } =>
+                format!("User limit successfully changed to `{expression}`!"),
+            | Self::UserLimitReset => "User limit successfully reset to the capacity!".to_owned(),
+            | Self::NumberingChanged { strategy } =>
+                format!("Numbering successfully changed to `{strategy}`!"),
+            | Self::NumberingGroupChanged { group } =>
+                format!("Numbering group successfully changed to `{group}`!"),
+            | Self::NumberingGroupLeft => "Numbering group successfully left!".to_owned(),
+            | Self::FirstNumberChanged { number } =>
+                format!("First number successfully changed to {number}!"),
+            | Self::MaxChildrenChanged { max } =>
+                format!("Maximum number of channels successfully changed to {max}!"),
+            | Self::MaxChildrenRemoved =>
+                "Maximum number of channels successfully removed!".to_owned(),
+            | Self::GracePeriodChanged { seconds } =>
+                format!("Empty channels are now deleted after {seconds} seconds!"),
+            | Self::GracePeriodRemoved => "Empty channels are now deleted right away!".to_owned(),
+            | Self::UnknownTimeZone { time_zone } => format!(
+                "Unknown time zone `{time_zone}`! Use an IANA name such as `Europe/Stockholm`."
+            ),
+            | Self::TimeZoneChanged { time_zone } =>
+                format!("Successfully set time zone to `{time_zone}`!"),
+            | Self::LocaleChanged => "I will speak English from now on!".to_owned(),
+            | Self::NameEmpty => "Names can't be empty!".to_owned(),
+            | Self::NameTooLong => "Names can be at most 32 characters long!".to_owned(),
+            | Self::NameInvalidCharacters =>
+                "Names may only contain letters, digits and underscores!".to_owned(),
+            | Self::VariableValueTooLong =>
+                "Variable values can be at most 100 characters long!".to_owned(),
+            | Self::VariableValueControlCharacters =>
+                "Variable values can't contain control characters!".to_owned(),
+            | Self::VariableSet { name, value } =>
+                format!("Successfully set `{{${name}}}` to `{value}`!"),
+            | Self::VariableDeleted { name } => format!("Successfully deleted `{{${name}}}`!"),
+            | Self::NoSuchVariable { name } => format!("There is no variable named `{name}`!"),
+            | Self::NoVariables => "No variables are defined in this guild.".to_owned(),
+            | Self::Variables => "Variables:".to_owned(),
+            | Self::NamePoolEmpty => "Name pools need at least one entry!".to_owned(),
+            | Self::NamePoolTooLarge => "Name pools can have at most 100 entries!".to_owned(),
+            | Self::PoolEntryTooLong =>
+                "Pool entries can be at most 100 characters long!".to_owned(),
+            | Self::PoolEntryControlCharacters =>
+                "Pool entries can't contain control characters!".to_owned(),
+            | Self::PoolEntriesNotUnique => "Pool entries must be unique!".to_owned(),
+            | Self::NamePoolSet { name, entries } =>
+                format!("Successfully set name pool `{name}` with {entries} entries!"),
+            | Self::NamePoolDeleted { name } => format!("Successfully deleted name pool `{name}`!"),
+            | Self::NoSuchNamePool { name } => format!("There is no name pool named `{name}`!"),
+            | Self::NoNamePools => "No name pools are defined in this guild.".to_owned(),
+            | Self::NamePools => "Name pools:".to_owned(),
+            | Self::ListedParent {
+                number,
+                name,
+                template,
+            } => format!("Parent {number}: \"{name}\" with template \"{template}\""),
+            | Self::ListedChild { number, name } => format!("Child {number}: \"{name}\""),
+            | Self::LobbyFull { parent } => format!(
+                "All channels of `{parent}` are full right now. Try again once someone leaves!"
+            ),
+            | Self::NotInChild =>
+                "You have to be connected to a generated channel to use this command!".to_owned(),
+            | Self::NotChannelOwner =>
+                "Only the owner of this channel can use this command!".to_owned(),
+            | Self::ChannelNameInvalid =>
+                "Channel names can't be blank or contain control characters!".to_owned(),
+            | Self::ChannelRenamed { name } => format!("Channel successfully renamed to `{name}`!"),
+            | Self::ChannelNameReset =>
+                "Channel name successfully reset to the template!".to_owned(),
+            | Self::ChannelLimitChanged { limit: 0 } =>
+                "User limit of the channel successfully removed!".to_owned(),
+            | Self::ChannelLimitChanged { limit } =>
+                format!("User limit of the channel successfully changed to {limit}!"),
+            | Self::ChannelLimitReset =>
+                "User limit of the channel successfully reset to the template!".to_owned(),
+            | Self::ChannelBitrateChanged { kbps } =>
+                format!("Bitrate of the channel successfully changed to {kbps} kbps!"),
+            | Self::ChannelBitrateReset =>
+                "Bitrate of the channel successfully reset to the template!".to_owned(),
+            | Self::MemberKicked { member } =>
+                format!("Successfully kicked `{member}` from the channel!"),
+            | Self::MemberNotInChannel { member } =>
+                format!("`{member}` isn't connected to your channel!"),
+            | Self::CannotKickSelf => "You can't kick yourself!".to_owned(),
+            | Self::OwnershipTransferChanged { transfer } =>
+                format!("Ownership transfer successfully changed to `{transfer}`!"),
+            | Self::AlreadyOwner => "You already own this channel!".to_owned(),
+            | Self::OwnerStillPresent =>
+                "The owner of this channel is still connected to it!".to_owned(),
+            | Self::ClaimTooEarly { seconds } => format!(
+                "The owner has to have been gone for {seconds} seconds before this channel can be \
+                 claimed!"
+            ),
+            | Self::ChannelClaimed => "You now own this channel!".to_owned(),
+            | Self::ChannelLocked =>
+                "Channel successfully locked! Only the members in it can join.".to_owned(),
+            | Self::ChannelHidden =>
+                "Channel successfully hidden! Only the members in it can see it.".to_owned(),
+            | Self::ChannelUnlocked => "Channel successfully unlocked!".to_owned(),
+            | Self::MemberPermitted { member } =>
+                format!("`{member}` can now see and join the channel!"),
+            | Self::PermitDeniedByParent { member } => format!(
+                "`{member}` isn't allowed to see and join the channel this one was created from!"
+            ),
+            | Self::MemberRejected { member } =>
+                format!("`{member}` can no longer see or join the channel!"),
+            | Self::CannotRejectSelf => "You can't reject yourself!".to_owned(),
+            | Self::InheritanceChanged {
+                setting,
+                inherit: true,
+            } => format!("New children will now inherit `{setting}` from this channel!"),
+            | Self::InheritanceChanged {
+                setting,
+                inherit: false,
+            } => format!("New children will no longer inherit `{setting}` from this channel!"),
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn swedish(&self) -> String {
+        match *self {
+            | Self::InvalidTemplate => "Ogiltig mall!".to_owned(),
+            | Self::TemplateChanged { template } => format!("Mallen är nu `{template}`!"),
+            | Self::ChannelCreated { name, template } =>
+                format!("Kanalen `{name}` har skapats med mallen `{template}`!"),
+            | Self::PreviewOf { template } => format!("Förhandsvisning av `{template}`:"),
+            | Self::EmptyNameWarning { child } =>
+                format!("⚠️ Kanal {child} skulle få ett tomt namn, vilket Discord inte tillåter."),
+            | Self::TooLongWarning { child, length, max } => format!(
+                "⚠️ Kanal {child} skulle bli {length} tecken lång och kommer att kortas till \
+                 {max}."
+            ),
+            | Self::CapacityChanged { capacity } => format!("Kapaciteten är nu {capacity}!"),
+            | Self::CapacityCleared { channel_id } =>
+                format!("Kapaciteten för kanalen med ID {channel_id} har tagits bort!"),
+            | Self::StatusTemplateChanged { template } =>
+                format!("Statusmallen är nu `{template}`!"),
+            | Self::StatusTemplateRemoved => "Statusmallen har tagits bort!".to_owned(),
+            | Self::BitrateChanged { expression } =>
+                format!("Bithastigheten är nu `{expression}` kbps!"),
+            | Self::BitrateReset => "Bithastigheten har återställts!".to_owned(),
+            | Self::UserLimitChanged { expression } =>
+                format!("Användargränsen är nu `{expression}`!"),
+            | Self::UserLimitReset =>
+                "Användargränsen har återställts till kapaciteten!".to_owned(),
+            | Self::NumberingChanged { strategy } => format!("Numreringen är nu `{strategy}`!"),
+            | Self::NumberingGroupChanged { group } =>
+                format!("Numreringsgruppen är nu `{group}`!"),
+            | Self::NumberingGroupLeft => "Numreringsgruppen har lämnats!".to_owned(),
+            | Self::FirstNumberChanged { number } => format!("Första numret är nu {number}!"),
+            | Self::MaxChildrenChanged { max } => format!("Högsta antalet kanaler är nu {max}!"),
+            | Self::MaxChildrenRemoved => "Högsta antalet kanaler har tagits bort!".to_owned(),
+            | Self::GracePeriodChanged { seconds } =>
+                format!("Tomma kanaler tas nu bort efter {seconds} sekunder!"),
+            | Self::GracePeriodRemoved => "Tomma kanaler tas nu bort direkt!".to_owned(),
+            | Self::UnknownTimeZone { time_zone } =>
+                format!("Okänd tidszon `{time_zone}`! Använd ett IANA-namn som `Europe/Stockholm`."),
+            | Self::TimeZoneChanged { time_zone } => format!("Tidszonen är nu `{time_zone}`!"),
+            | Self::LocaleChanged => "Jag pratar svenska från och med nu!".to_owned(),
+            | Self::NameEmpty => "Namn får inte vara tomma!".to_owned(),
+            | Self::NameTooLong => "Namn får vara högst 32 tecken långa!".to_owned(),
+            | Self::NameInvalidCharacters =>
+                "Namn får bara innehålla bokstäver, siffror och understreck!".to_owned(),
+            | Self::VariableValueTooLong =>
+                "Variabelvärden får vara högst 100 tecken långa!".to_owned(),
+            | Self::VariableValueControlCharacters =>
+                "Variabelvärden får inte innehålla kontrolltecken!".to_owned(),
+            | Self::VariableSet { name, value } => format!("`{{${name}}}` är nu `{value}`!"),
+            | Self::VariableDeleted { name } => format!("`{{${name}}}` har tagits bort!"),
+            | Self::NoSuchVariable { name } =>
+                format!("Det finns ingen variabel som heter `{name}`!"),
+            | Self::NoVariables => "Inga variabler är definierade på den här servern.".to_owned(),
+            | Self::Variables => "Variabler:".to_owned(),
+            | Self::NamePoolEmpty => "Namnpooler behöver minst ett namn!".to_owned(),
+            | Self::NamePoolTooLarge => "Namnpooler får ha högst 100 namn!".to_owned(),
+            | Self::PoolEntryTooLong => "Namn i pooler får vara högst 100 tecken långa!".to_owned(),
+            | Self::PoolEntryControlCharacters =>
+                "Namn i pooler får inte innehålla kontrolltecken!".to_owned(),
+            | Self::PoolEntriesNotUnique => "Namn i pooler måste vara unika!".to_owned(),
+            | Self::NamePoolSet { name, entries } =>
+                format!("Namnpoolen `{name}` har nu {entries} namn!"),
+            | Self::NamePoolDeleted { name } => format!("Namnpoolen `{name}` har tagits bort!"),
+            | Self::NoSuchNamePool { name } =>
+                format!("Det finns ingen namnpool som heter `{name}`!"),
+            | Self::NoNamePools => "Inga namnpooler är definierade på den här servern.".to_owned(),
+            | Self::NamePools => "Namnpooler:".to_owned(),
+            | Self::ListedParent {
+                number,
+                name,
+                template,
+            } => format!("Förälder {number}: \"{name}\" med mallen \"{template}\""),
+            | Self::ListedChild { number, name } => format!("Kanal {number}: \"{name}\""),
+            | Self::LobbyFull { parent } => format!(
+                "Alla kanaler i `{parent}` är fulla just nu. Försök igen när någon har gått!"
+            ),
+            | Self::NotInChild => "Du måste vara ansluten till en genererad kanal för att använda \
+                                   det här kommandot!"
+                .to_owned(),
+            | Self::NotChannelOwner =>
+                "Bara kanalens ägare kan använda det här kommandot!".to_owned(),
+            | Self::ChannelNameInvalid =>
+                "Kanalnamn får inte vara tomma eller innehålla kontrolltecken!".to_owned(),
+            | Self::ChannelRenamed { name } => format!("Kanalen heter nu `{name}`!"),
+            | Self::ChannelNameReset => "Kanalnamnet följer nu mallen igen!".to_owned(),
+            | Self::ChannelLimitChanged { limit: 0 } =>
+                "Kanalens användargräns har tagits bort!".to_owned(),
+            | Self::ChannelLimitChanged { limit } =>
+                format!("Kanalens användargräns är nu {limit}!"),
+            | Self::ChannelLimitReset => "Kanalens användargräns följer nu mallen igen!".to_owned(),
+            | Self::ChannelBitrateChanged { kbps } =>
+                format!("Kanalens bithastighet är nu {kbps} kbps!"),
+            | Self::ChannelBitrateReset =>
+                "Kanalens bithastighet följer nu mallen igen!".to_owned(),
+            | Self::MemberKicked { member } => format!("`{member}` har sparkats ut ur kanalen!"),
+            | Self::MemberNotInChannel { member } =>
+                format!("`{member}` är inte ansluten till din kanal!"),
+            | Self::CannotKickSelf => "Du kan inte sparka ut dig själv!".to_owned(),
+            | Self::OwnershipTransferChanged { transfer } =>
+                format!("Ägarbyte är nu `{transfer}`!"),
+            | Self::AlreadyOwner => "Du äger redan den här kanalen!".to_owned(),
+            | Self::OwnerStillPresent =>
+                "Kanalens ägare är fortfarande ansluten till den!".to_owned(),
+            | Self::ClaimTooEarly { seconds } => format!(
+                "Ägaren måste ha varit borta i {seconds} sekunder innan kanalen kan tas över!"
+            ),
+            | Self::ChannelClaimed => "Du äger nu den här kanalen!".to_owned(),
+            | Self::ChannelLocked =>
+                "Kanalen är nu låst! Bara de som är i den kan ansluta.".to_owned(),
+            | Self::ChannelHidden =>
+                "Kanalen är nu dold! Bara de som är i den kan se den.".to_owned(),
+            | Self::ChannelUnlocked => "Kanalen är nu upplåst!".to_owned(),
+            | Self::MemberPermitted { member } =>
+                format!("`{member}` kan nu se och ansluta till kanalen!"),
+            | Self::PermitDeniedByParent { member } => format!(
+                "`{member}` får inte se och ansluta till kanalen som den här skapades från!"
+            ),
+            | Self::MemberRejected { member } =>
+                format!("`{member}` kan inte längre se eller ansluta till kanalen!"),
+            | Self::CannotRejectSelf => "Du kan inte neka dig själv!".to_owned(),
+            | Self::InheritanceChanged {
+                setting,
+                inherit: true,
+            } => format!("Nya underkanaler ärver nu `{setting}` från den här kanalen!"),
+            | Self::InheritanceChanged {
+                setting,
+                inherit: false,
+            } => format!("Nya underkanaler ärver inte längre `{setting}` från den här kanalen!"),
+        }
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn german(&self) -> String {
+        match *self {
+            | Self::InvalidTemplate => "Ungültige Vorlage!".to_owned(),
+            | Self::TemplateChanged { template } =>
+                format!("Vorlage erfolgreich zu `{template}` geändert!"),
+            | Self::ChannelCreated { name, template } =>
+                format!("Kanal `{name}` erfolgreich mit der Vorlage `{template}` erstellt!"),
+            | Self::PreviewOf { template } => format!("Vorschau von `{template}`:"),
+            | Self::EmptyNameWarning { child } =>
+                format!("⚠️ Kanal {child} hätte einen leeren Namen, den Discord nicht akzeptiert."),
+            | Self::TooLongWarning { child, length, max } =>
+                format!("⚠️ Kanal {child} wäre {length} Zeichen lang und wird auf {max} gekürzt."),
+            | Self::CapacityChanged { capacity } =>
+                format!("Kapazität erfolgreich zu {capacity} geändert!"),
+            | Self::CapacityCleared { channel_id } =>
+                format!("Kapazität des Kanals mit der ID {channel_id} erfolgreich entfernt!"),
+            | Self::StatusTemplateChanged { template } =>
+                format!("Statusvorlage erfolgreich zu `{template}` geändert!"),
+            | Self::StatusTemplateRemoved => "Statusvorlage erfolgreich entfernt!".to_owned(),
+            | Self::BitrateChanged { expression } =>
+                format!("Bitrate erfolgreich zu `{expression}` kbps geändert!"),
+            | Self::BitrateReset => "Bitrate erfolgreich zurückgesetzt!".to_owned(),
+            | Self::UserLimitChanged { expression } =>
+                format!("Benutzerlimit erfolgreich zu `{expression}` geändert!"),
+            | Self::UserLimitReset =>
+                "Benutzerlimit erfolgreich auf die Kapazität zurückgesetzt!".to_owned(),
+            | Self::NumberingChanged { strategy } =>
+                format!("Nummerierung erfolgreich zu `{strategy}` geändert!"),
+            | Self::NumberingGroupChanged { group } =>
+                format!("Nummerierungsgruppe erfolgreich zu `{group}` geändert!"),
+            | Self::NumberingGroupLeft => "Nummerierungsgruppe erfolgreich verlassen!".to_owned(),
+            | Self::FirstNumberChanged { number } =>
+                format!("Erste Nummer erfolgreich zu {number} geändert!"),
+            | Self::MaxChildrenChanged { max } =>
+                format!("Höchstzahl an Kanälen erfolgreich zu {max} geändert!"),
+            | Self::MaxChildrenRemoved => "Höchstzahl an Kanälen erfolgreich entfernt!".to_owned(),
+            | Self::GracePeriodChanged { seconds } =>
+                format!("Leere Kanäle werden jetzt nach {seconds} Sekunden gelöscht!"),
+            | Self::GracePeriodRemoved => "Leere Kanäle werden jetzt sofort gelöscht!".to_owned(),
+            | Self::UnknownTimeZone { time_zone } => format!(
+                "Unbekannte Zeitzone `{time_zone}`! Verwende einen IANA-Namen wie `Europe/Berlin`."
+            ),
+            | Self::TimeZoneChanged { time_zone } =>
+                format!("Zeitzone erfolgreich zu `{time_zone}` geändert!"),
+            | Self::LocaleChanged => "Ab jetzt spreche ich Deutsch!".to_owned(),
+            | Self::NameEmpty => "Namen dürfen nicht leer sein!".to_owned(),
+            | Self::NameTooLong => "Namen dürfen höchstens 32 Zeichen lang sein!".to_owned(),
+            | Self::NameInvalidCharacters =>
+                "Namen dürfen nur Buchstaben, Ziffern und Unterstriche enthalten!".to_owned(),
+            | Self::VariableValueTooLong =>
+                "Variablenwerte dürfen höchstens 100 Zeichen lang sein!".to_owned(),
+            | Self::VariableValueControlCharacters =>
+                "Variablenwerte dürfen keine Steuerzeichen enthalten!".to_owned(),
+            | Self::VariableSet { name, value } =>
+                format!("`{{${name}}}` erfolgreich auf `{value}` gesetzt!"),
+            | Self::VariableDeleted { name } => format!("`{{${name}}}` erfolgreich gelöscht!"),
+            | Self::NoSuchVariable { name } => format!("Es gibt keine Variable namens `{name}`!"),
+            | Self::NoVariables => "Auf diesem Server sind keine Variablen definiert.".to_owned(),
+            | Self::Variables => "Variablen:".to_owned(),
+            | Self::NamePoolEmpty => "Namenspools brauchen mindestens einen Eintrag!".to_owned(),
+            | Self::NamePoolTooLarge =>
+                "Namenspools dürfen höchstens 100 Einträge haben!".to_owned(),
+            | Self::PoolEntryTooLong =>
+                "Pooleinträge dürfen höchstens 100 Zeichen lang sein!".to_owned(),
+            | Self::PoolEntryControlCharacters =>
+                "Pooleinträge dürfen keine Steuerzeichen enthalten!".to_owned(),
+            | Self::PoolEntriesNotUnique => "Pooleinträge müssen eindeutig sein!".to_owned(),
+            | Self::NamePoolSet { name, entries } =>
+                format!("Namenspool `{name}` erfolgreich mit {entries} Einträgen gesetzt!"),
+            | Self::NamePoolDeleted { name } =>
+                format!("Namenspool `{name}` erfolgreich gelöscht!"),
+            | Self::NoSuchNamePool { name } =>
+                format!("Es gibt keinen Namenspool namens `{name}`!"),
+            | Self::NoNamePools => "Auf diesem Server sind keine Namenspools definiert.".to_owned(),
+            | Self::NamePools => "Namenspools:".to_owned(),
+            | Self::ListedParent {
+                number,
+                name,
+                template,
+            } => format!("Elternkanal {number}: \"{name}\" mit der Vorlage \"{template}\""),
+            | Self::ListedChild { number, name } => format!("Kanal {number}: \"{name}\""),
+            | Self::LobbyFull { parent } => format!(
+                "Alle Kanäle von `{parent}` sind gerade voll. Versuch es noch einmal, sobald \
+                 jemand gegangen ist!"
+            ),
+            | Self::NotInChild => "Du musst mit einem erzeugten Kanal verbunden sein, um diesen \
+                                   Befehl zu verwenden!"
+                .to_owned(),
+            | Self::NotChannelOwner =>
+                "Nur der Besitzer dieses Kanals kann diesen Befehl verwenden!".to_owned(),
+            | Self::ChannelNameInvalid =>
+                "Kanalnamen dürfen nicht leer sein oder Steuerzeichen enthalten!".to_owned(),
+            | Self::ChannelRenamed { name } => format!("Kanal erfolgreich in `{name}` umbenannt!"),
+            | Self::ChannelNameReset =>
+                "Kanalname erfolgreich auf die Vorlage zurückgesetzt!".to_owned(),
+            | Self::ChannelLimitChanged { limit: 0 } =>
+                "Benutzerlimit des Kanals erfolgreich entfernt!".to_owned(),
+            | Self::ChannelLimitChanged { limit } =>
+                format!("Benutzerlimit des Kanals erfolgreich zu {limit} geändert!"),
+            | Self::ChannelLimitReset =>
+                "Benutzerlimit des Kanals erfolgreich auf die Vorlage zurückgesetzt!".to_owned(),
+            | Self::ChannelBitrateChanged { kbps } =>
+                format!("Bitrate des Kanals erfolgreich zu {kbps} kbps geändert!"),
+            | Self::ChannelBitrateReset =>
+                "Bitrate des Kanals erfolgreich auf die Vorlage zurückgesetzt!".to_owned(),
+            | Self::MemberKicked { member } =>
+                format!("`{member}` wurde erfolgreich aus dem Kanal geworfen!"),
+            | Self::MemberNotInChannel { member } =>
+                format!("`{member}` ist nicht mit deinem Kanal verbunden!"),
+            | Self::CannotKickSelf => "Du kannst dich nicht selbst rauswerfen!".to_owned(),
+            | Self::OwnershipTransferChanged { transfer } =>
+                format!("Besitzerwechsel erfolgreich zu `{transfer}` geändert!"),
+            | Self::AlreadyOwner => "Dieser Kanal gehört dir bereits!".to_owned(),
+            | Self::OwnerStillPresent =>
+                "Der Besitzer dieses Kanals ist noch mit ihm verbunden!".to_owned(),
+            | Self::ClaimTooEarly { seconds } => format!(
+                "Der Besitzer muss seit {seconds} Sekunden weg sein, bevor dieser Kanal \
+                 übernommen werden kann!"
+            ),
+            | Self::ChannelClaimed => "Dieser Kanal gehört jetzt dir!".to_owned(),
+            | Self::ChannelLocked =>
+                "Kanal erfolgreich gesperrt! Nur die Mitglieder darin können beitreten.".to_owned(),
+            | Self::ChannelHidden =>
+                "Kanal erfolgreich versteckt! Nur die Mitglieder darin können ihn sehen.".to_owned(),
+            | Self::ChannelUnlocked => "Kanal erfolgreich entsperrt!".to_owned(),
+            | Self::MemberPermitted { member } =>
+                format!("`{member}` kann den Kanal jetzt sehen und ihm beitreten!"),
+            | Self::PermitDeniedByParent { member } => format!(
+                "`{member}` darf den Kanal, aus dem dieser erstellt wurde, nicht sehen und ihm \
+                 nicht beitreten!"
+            ),
+            | Self::MemberRejected { member } =>
+                format!("`{member}` kann den Kanal nicht mehr sehen oder ihm beitreten!"),
+            | Self::CannotRejectSelf => "Du kannst dich nicht selbst ablehnen!".to_owned(),
+            | Self::InheritanceChanged {
+                setting,
+                inherit: true,
+            } => format!("Neue Unterkanäle übernehmen jetzt `{setting}` von diesem Kanal!"),
+            | Self::InheritanceChanged {
+                setting,
+                inherit: false,
+            } => format!("Neue Unterkanäle übernehmen `{setting}` nicht mehr von diesem Kanal!"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(Locale::English)]
+    #[case(Locale::Swedish)]
+    #[case(Locale::German)]
+    fn test_code_round_trips(#[case] locale: Locale) {
+        assert_eq!(Some(locale), Locale::from_code(locale.code()));
+    }
+
+    #[rstest]
+    #[case(Locale::English, "There is no variable named `season`!")]
+    #[case(Locale::Swedish, "Det finns ingen variabel som heter `season`!")]
+    #[case(Locale::German, "Es gibt keine Variable namens `season`!")]
+    fn test_localize(#[case] locale: Locale, #[case] expected: &str) {
+        let reply = Reply::NoSuchVariable { name: "season" };
+        assert_eq!(expected, reply.localize(locale));
+    }
+}
@@ -16,6 +16,7 @@ use std::{
         Arc,
         LazyLock,
     },
+    time::Instant,
 };
 /// Utility trait to drop a value. Semantically equivalent to `std::mem::drop`.
 pub(crate) trait DropExt {
@@ -68,6 +69,7 @@ use poise::{
 };
 use serenity::{
     all::{
+        ChannelId,
         Context as SerenityContext,
         CreateAllowedMentions,
         GatewayIntents,
@@ -83,6 +85,7 @@ use sqlx::PgPool;
 use tokio::{
     runtime::Builder,
     sync::RwLock,
+    task::AbortHandle,
 };
 #[allow(unused_imports)]
 use tracing::{
@@ -115,16 +118,38 @@ use voice_channels::{
         change_capacity,
         clear_capacity,
         create_channel,
+        delete_name_pool,
+        delete_variable,
+        list_name_pools,
         list_template_channels,
+        list_variables,
+        preview_template,
+        set_bitrate,
+        set_first_number,
+        set_grace_period,
+        set_inheritance,
+        set_locale,
+        set_max_children,
+        set_name_pool,
+        set_numbering,
+        set_numbering_group,
+        set_ownership_transfer,
+        set_status_template,
+        set_time_zone,
+        set_user_limit,
+        set_variable,
     },
     db::{
         Children,
         Parent,
+        TemplateCache,
     },
+    owner_commands::vc,
 };
 
 mod db;
 mod events;
+mod locale;
 mod util;
 mod voice_channels;
 
@@ -149,13 +174,40 @@ impl TypeMapKey for GuildChannels {
 struct VoiceStates;
 
 impl TypeMapKey for VoiceStates {
-    type Value = Arc<RwLock<HashMap<GuildId, Arc<RwLock<HashMap<UserId, VoiceState>>>>>>;
+    type Value = Arc<RwLock<HashMap<GuildId, Arc<RwLock<HashMap<UserId, TrackedVoiceState>>>>>>;
+}
+
+/// The voice state of a member along with when they joined its channel.
+#[derive(Debug, Clone)]
+struct TrackedVoiceState {
+    state:     VoiceState,
+    /// When the member joined the channel, or when the bot first saw them in
+    /// it.
+    joined_at: Instant,
+}
+
+/// The tasks that delete empty children once their grace period is over.
+struct PendingDeletions;
+
+impl TypeMapKey for PendingDeletions {
+    type Value = Arc<RwLock<HashMap<ChannelId, AbortHandle>>>;
+}
+
+/// The parsed templates of every parent.
+struct ParsedTemplates;
+
+impl TypeMapKey for ParsedTemplates {
+    type Value = Arc<TemplateCache>;
 }
 
 pub(crate) async fn get_db_handle(ctx: &SerenityContext) -> PgPool {
     ctx.data.read().await.get::<DBConnection>().unwrap().clone()
 }
 
+pub(crate) async fn get_template_cache(ctx: &SerenityContext) -> Arc<TemplateCache> {
+    Arc::clone(ctx.data.read().await.get::<ParsedTemplates>().unwrap())
+}
+
 pub(crate) static CLIENT_ID: LazyLock<UserId> = LazyLock::new(|| {
     var("DISCORD_CLIENT_ID")
         .wrap_err_with(|| eyre!("Reading discord client id environment variable failed!"))
@@ -189,8 +241,7 @@ fn main() -> Result<()> {
             EnvFilter::from_str(
                 rust_log
                     .as_ref()
-                    .map(String::as_str)
-                    .unwrap_or("voice_channel_manager=debug,info"),
+                    .map_or("voice_channel_manager=debug,info", String::as_str),
             )
             .wrap_err_with(|| {
                 eyre!(
@@ -283,7 +334,8 @@ async fn on_error(error: FrameworkError<'_>) {
     let to_send = match error {
         | FrameworkError::Command { error, ctx, .. } =>
             format!("Running command {} failed: {error:?}", ctx.command().name),
-        | FrameworkError::SubcommandRequired { .. } => unreachable!("We don't use subcommands"),
+        | FrameworkError::SubcommandRequired { ctx } =>
+            format!("Command {} requires a subcommand", ctx.command().name),
         | FrameworkError::CommandPanic { payload, ctx, .. } => format!(
             "Running command {} panicked: {}",
             ctx.command().name,
@@ -427,7 +479,27 @@ async fn start() -> Result<()> {
                 create_channel(),
                 change_capacity(),
                 clear_capacity(),
+                set_status_template(),
+                set_bitrate(),
+                set_user_limit(),
+                set_numbering(),
+                set_numbering_group(),
+                set_first_number(),
+                set_max_children(),
+                set_grace_period(),
+                set_ownership_transfer(),
+                set_inheritance(),
                 list_template_channels(),
+                preview_template(),
+                set_time_zone(),
+                set_locale(),
+                set_variable(),
+                delete_variable(),
+                list_variables(),
+                set_name_pool(),
+                delete_name_pool(),
+                list_name_pools(),
+                vc(),
             ],
             ..Default::default()
         })
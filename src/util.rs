@@ -8,6 +8,8 @@ use serenity::{
         ChannelId,
         GuildChannel,
         GuildId,
+        Member,
+        UserId,
     },
     prelude::{
         TypeMap,
@@ -16,6 +18,33 @@ use serenity::{
 };
 use tokio::sync::RwLock;
 
+/// The maximum length of a message allowed by Discord, counted in characters.
+pub(crate) const MAX_MESSAGE_LENGTH: usize = 2000;
+
+/// Truncates text to at most `max_length` characters, ending it with an
+/// ellipsis if anything was cut off.
+pub(crate) fn truncate_with_ellipsis(text: &str, max_length: usize) -> String {
+    if text.chars().count() <= max_length {
+        return text.to_owned();
+    }
+    let mut truncated = text
+        .chars()
+        .take(max_length.saturating_sub(1))
+        .collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+/// Wraps text in a Discord code block of at most `max_length` characters,
+/// truncating the text if needed. A zero width space is put between
+/// consecutive backticks, so that backticks in the text can't end the block.
+pub(crate) fn code_block(text: &str, max_length: usize) -> String {
+    let escaped = text.replace("``", "`\u{200B}`").replace("``", "`\u{200B}`");
+    // The fences and the line breaks around the text take up 8 characters.
+    let text = truncate_with_ellipsis(&escaped, max_length.saturating_sub(8));
+    format!("```\n{text}\n```")
+}
+
 pub(crate) async fn get_value<T>(map: &RwLock<TypeMap>) -> T::Value
 where
     T: TypeMapKey,
@@ -27,6 +56,7 @@ where
 
 pub(crate) trait CacheExt {
     fn guild_channel(&self, guild_id: GuildId, channel_id: ChannelId) -> Result<GuildChannel>;
+    fn guild_member(&self, guild_id: GuildId, user_id: UserId) -> Option<Member>;
 }
 
 impl CacheExt for Cache {
@@ -39,4 +69,44 @@ impl CacheExt for Cache {
             .ok_or_else(|| eyre!("No channel found!"))?
             .clone())
     }
+
+    fn guild_member(&self, guild_id: GuildId, user_id: UserId) -> Option<Member> {
+        self.guild(guild_id)?.members.get(&user_id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("{ownr}", "```\n{ownr}\n```")]
+    #[case("`owner`", "```\n`owner`\n```")]
+    #[case("a```b", "```\na`\u{200B}`\u{200B}`b\n```")]
+    #[case("````", "```\n`\u{200B}`\u{200B}`\u{200B}`\n```")]
+    fn test_code_block(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(expected, code_block(text, MAX_MESSAGE_LENGTH));
+    }
+
+    #[rstest]
+    #[case("Lobby", 5, "Lobby")]
+    #[case("Lobby 1", 5, "Lobb…")]
+    #[case("Röstkanal", 4, "Rös…")]
+    fn test_truncate_with_ellipsis(
+        #[case] text: &str,
+        #[case] max_length: usize,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(expected, truncate_with_ellipsis(text, max_length));
+    }
+
+    #[test]
+    fn test_code_block_fits() {
+        let block = code_block(&"a".repeat(3000), 100);
+        assert_eq!(100, block.chars().count());
+        assert!(block.ends_with("…\n```"));
+    }
 }
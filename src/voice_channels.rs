@@ -1,4 +1,7 @@
 pub(crate) mod commands;
 pub(crate) mod db;
+pub(crate) mod expr;
+pub(crate) mod format;
+pub(crate) mod owner_commands;
 pub(crate) mod parser;
 pub(crate) mod updater;
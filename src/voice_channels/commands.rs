@@ -1,11 +1,22 @@
-use std::fmt::Write;
+use std::{
+    fmt::Write,
+    time::Duration,
+};
 
+use chrono::Utc;
+use chrono_tz::{
+    Tz,
+    TZ_VARIANTS,
+};
 use eyre::{
     eyre,
     Result,
     WrapErr,
 };
-use poise::command;
+use poise::{
+    command,
+    ChoiceParameter,
+};
 use serde_json::Number;
 use serenity::{
     json::JsonMap,
@@ -21,15 +32,94 @@ use tracing::{
     trace_span,
 };
 
+use super::{
+    db::{
+        ChildSetting,
+        NumberingStrategy,
+        OwnershipTransfer,
+        Parent,
+    },
+    parser::TemplateError,
+    updater::{
+        render,
+        truncate_channel_name,
+        OwnerInfo,
+        RenderContext,
+        RenderState,
+        MAX_CHANNEL_NAME_LENGTH,
+    },
+};
 use crate::{
+    db::{
+        get_locale,
+        get_time_zone,
+        set_locale as store_locale,
+        set_time_zone as store_time_zone,
+    },
+    events::{
+        clear_child_statuses,
+        update_children,
+        update_children_using_variable,
+        update_locale_dependent_children,
+        update_numbering_scope,
+        update_time_dependent_children,
+    },
     get_db_handle,
-    util::CacheExt,
+    get_template_cache,
+    locale::{
+        Locale,
+        Reply,
+    },
+    util::{
+        code_block,
+        truncate_with_ellipsis,
+        CacheExt,
+        MAX_MESSAGE_LENGTH,
+    },
     Context,
     DropExt,
+    HashSet,
 };
 
 type CommandResult = Result<()>;
 
+/// The maximum length of a variable or name pool name.
+const MAX_NAME_LENGTH: usize = 32;
+/// The maximum number of entries in a name pool.
+const MAX_POOL_ENTRIES: usize = 100;
+
+/// Returns the locale the bot replies in for the guild the command was
+/// invoked in.
+pub(crate) async fn guild_locale(ctx: Context<'_>) -> Result<Locale> {
+    get_locale(
+        &get_db_handle(ctx.serenity_context()).await,
+        ctx.guild_id().unwrap(),
+    )
+    .await
+    .wrap_err_with(|| eyre!("Failed to retrieve locale!"))
+}
+
+/// Tells the author which part of their template failed to parse.
+async fn reply_with_template_error(ctx: Context<'_>, err: &TemplateError) -> CommandResult {
+    info!("Rejected template: {err}");
+    let locale = guild_locale(ctx).await?;
+    let header = format!(
+        "{}: {}\n",
+        ctx.author().mention(),
+        Reply::InvalidTemplate.localize(locale)
+    );
+    let snippet = code_block(
+        &err.render_snippet(),
+        MAX_MESSAGE_LENGTH.saturating_sub(header.chars().count()),
+    );
+    ctx.channel_id()
+        .say(&ctx.http(), format!("{header}{snippet}"))
+        .await
+        .wrap_err_with(|| eyre!("Failed to send message!"))?
+        .drop();
+    Ok(())
+}
+
 /// Alters the template for a template channel.
 #[command(
     slash_command,
@@ -47,23 +137,34 @@ pub(crate) async fn alter_template(
     async move {
         info!("New template: {new_template}!");
         let guild_id = ctx.guild().unwrap().id;
-        let parsed_template = super::parser::parse_template(&new_template)
-            .wrap_err_with(|| eyre!("Failed to parse template!"))?;
+        let parsed_template = match super::parser::parse_template(&new_template) {
+            | Ok(parsed_template) => parsed_template,
+            | Err(err) => return reply_with_template_error(ctx, &err).await,
+        };
         info!("Parsed template: {:#?}!", parsed_template);
         super::db::set_template(
             &get_db_handle(ctx.serenity_context()).await,
             channel_id,
             guild_id,
-            new_template.clone(),
+            &parsed_template,
         )
         .await
         .wrap_err_with(|| eyre!("Failed to set template!"))?;
+        get_template_cache(ctx.serenity_context())
+            .await
+            .evict_parents(guild_id, [channel_id])
+            .await;
+        let locale = guild_locale(ctx).await?;
         ctx.channel_id()
             .say(
                 &ctx.http(),
                 format!(
-                    "{}: Template successfully changed to `{new_template}`!",
-                    ctx.author().mention()
+                    "{}: {}",
+                    ctx.author().mention(),
+                    Reply::TemplateChanged {
+                        template: &parsed_template.to_string(),
+                    }
+                    .localize(locale)
                 ),
             )
             .await
@@ -91,15 +192,14 @@ pub(crate) async fn create_channel(
         trace!("Entered create_channel!");
         let guild_id = ctx.guild().unwrap().id;
         trace!("Template: {}!", template);
-        let parsed_template = super::parser::parse_template(&template)
-            .wrap_err_with(|| eyre!("Failed to parse template!"))?;
+        let parsed_template = match super::parser::parse_template(&template) {
+            | Ok(parsed_template) => parsed_template,
+            | Err(err) => return reply_with_template_error(ctx, &err).await,
+        };
         trace!("Parsed template: {:#?}!", parsed_template);
         let mut options = JsonMap::new();
         options
-            .insert(
-                "name".to_string(),
-                JsonValue::String(channel_name.to_string()),
-            )
+            .insert("name".to_string(), JsonValue::String(channel_name.clone()))
             .drop();
         options
             .insert("type".to_string(), JsonValue::Number(Number::from(2)))
@@ -114,17 +214,22 @@ pub(crate) async fn create_channel(
             &get_db_handle(ctx.serenity_context()).await,
             channel.id,
             guild_id,
-            template.to_string(),
+            &parsed_template,
         )
         .await
         .wrap_err_with(|| eyre!("Failed to create template!"))?;
+        let locale = guild_locale(ctx).await?;
         ctx.channel_id()
             .say(
                 &ctx.http(),
                 format!(
-                    "{}: Channel successfully created with name `{channel_name}` and template \
-                     `{template}`!",
-                    ctx.author().mention()
+                    "{}: {}",
+                    ctx.author().mention(),
+                    Reply::ChannelCreated {
+                        name:     &channel_name,
+                        template: &parsed_template.to_string(),
+                    }
+                    .localize(locale)
                 ),
             )
             .await
@@ -136,6 +241,140 @@ pub(crate) async fn create_channel(
     .instrument(span)
     .await
 }
+/// Returns why Discord would reject or truncate a rendered child name, if it
+/// would.
+fn name_warning(child: u64, name: &str) -> Option<Reply<'static>> {
+    let length = name.chars().count();
+    if name.trim().is_empty() {
+        Some(Reply::EmptyNameWarning { child })
+    } else if length > MAX_CHANNEL_NAME_LENGTH {
+        Some(Reply::TooLongWarning {
+            child,
+            length,
+            max: MAX_CHANNEL_NAME_LENGTH,
+        })
+    } else {
+        None
+    }
+}
+
+/// Previews the names a template would give to child channels, without
+/// creating any channels.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    aliases("preview"),
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn preview_template(
+    ctx: Context<'_>,
+    #[description = "The template to preview"] template: String,
+    #[description = "How many children to preview. Defaults to 3."]
+    #[min = 1]
+    #[max = 10]
+    children: Option<u64>,
+    #[description = "How many members are in each child. Defaults to 1."] users: Option<u64>,
+    #[description = "The capacity of each child. Unlimited by default."] capacity: Option<u64>,
+    #[description = "The game being played in each child."] game: Option<String>,
+) -> CommandResult {
+    let span = trace_span!("preview_template span");
+    async move {
+        let parsed_template = match super::parser::parse_template(&template) {
+            | Ok(parsed_template) => parsed_template,
+            | Err(err) => return reply_with_template_error(ctx, &err).await,
+        };
+        let children = children.unwrap_or(3).clamp(1, 10);
+        let owner = ctx.author_member().await.as_deref().map(OwnerInfo::from);
+        let time_zone = get_time_zone(
+            &get_db_handle(ctx.serenity_context()).await,
+            ctx.guild_id().unwrap(),
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to retrieve time zone!"))?;
+        let locale = guild_locale(ctx).await?;
+        let variables = super::db::get_variables(
+            &get_db_handle(ctx.serenity_context()).await,
+            ctx.guild_id().unwrap(),
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to retrieve variables!"))?;
+        let pool_entries = match parsed_template.pool_name() {
+            | Some(pool_name) => super::db::get_name_pools(
+                &get_db_handle(ctx.serenity_context()).await,
+                ctx.guild_id().unwrap(),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to retrieve name pools!"))?
+            .remove(pool_name)
+            .unwrap_or_default(),
+            | None => Vec::new(),
+        };
+        let now = Utc::now();
+        let mut message = format!(
+            "{}: {}\n",
+            ctx.author().mention(),
+            Reply::PreviewOf {
+                template: &template,
+            }
+            .localize(locale)
+        );
+        let mut warnings = String::new();
+        for channel_number in 1..=children {
+            let name = render(
+                &parsed_template,
+                &RenderContext {
+                    channel_number,
+                    total_children_number: children,
+                    owner: owner.clone(),
+                    game: game.clone(),
+                    users: users.unwrap_or(1),
+                    capacity,
+                    now,
+                    time_zone,
+                    locale,
+                    variables: variables.clone(),
+                    pool_entry: usize::try_from(channel_number - 1)
+                        .ok()
+                        .and_then(|idx| pool_entries.get(idx))
+                        .cloned(),
+                    state: RenderState::new(&parsed_template),
+                },
+            );
+            let name = match name {
+                | Ok(name) => name,
+                | Err(err) => {
+                    writeln!(message, "{channel_number}. ⚠️ {err}")
+                        .wrap_err_with(|| eyre!("Failed to write error to message!"))?;
+                    continue;
+                },
+            };
+            if let Some(warning) = name_warning(channel_number, &name) {
+                writeln!(warnings, "{}", warning.localize(locale))
+                    .wrap_err_with(|| eyre!("Failed to write warning to message!"))?;
+            }
+            writeln!(
+                message,
+                "{channel_number}. `{}`",
+                truncate_channel_name(&name)
+            )
+            .wrap_err_with(|| eyre!("Failed to write child name to message!"))?;
+        }
+        message.push_str(&warnings);
+        // Long templates can make the preview longer than Discord allows.
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                truncate_with_ellipsis(&message, MAX_MESSAGE_LENGTH),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
 /// Changes the capacity for generated channels of a template channel.
 #[command(
     slash_command,
@@ -163,12 +402,18 @@ pub(crate) async fn change_capacity(
         .await
         .wrap_err_with(|| eyre!("Failed at changing capacity!"))?;
 
+        update_children(ctx.serenity_context(), guild_id, &Parent::from(channel_id))
+            .await
+            .wrap_err_with(|| eyre!("Failed to update children after changing capacity!"))?;
+
+        let locale = guild_locale(ctx).await?;
         ctx.channel_id()
             .say(
                 &ctx.http(),
                 format!(
-                    "{} - Successfully changed capacity to {capacity}!",
-                    ctx.author().mention()
+                    "{} - {}",
+                    ctx.author().mention(),
+                    Reply::CapacityChanged { capacity }.localize(locale)
                 ),
             )
             .await
@@ -205,12 +450,21 @@ pub(crate) async fn clear_capacity(
         .await
         .wrap_err_with(|| eyre!("Failed at clearing capacity!"))?;
 
+        update_children(ctx.serenity_context(), guild_id, &Parent::from(channel_id))
+            .await
+            .wrap_err_with(|| eyre!("Failed to update children after clearing capacity!"))?;
+
+        let locale = guild_locale(ctx).await?;
         ctx.channel_id()
             .say(
                 &ctx.http(),
                 format!(
-                    "{} - Successfully cleared capacity for channel with ID {channel_id}!",
-                    ctx.author().mention()
+                    "{} - {}",
+                    ctx.author().mention(),
+                    Reply::CapacityCleared {
+                        channel_id: channel_id.get(),
+                    }
+                    .localize(locale)
                 ),
             )
             .await
@@ -222,45 +476,1168 @@ pub(crate) async fn clear_capacity(
     .instrument(span)
     .await
 }
-/// Lists all template channels and their children in your guild.
+/// Sets the template of the voice channel status of generated channels.
 #[command(
     slash_command,
     category = "voice-channels",
     guild_only,
-    aliases(
-        "list_template",
-        "list_templates",
-        "list_template_channel",
-        "list",
-        "list_channel",
-        "list_channels"
-    ),
+    aliases("set_status"),
     required_permissions = "MANAGE_CHANNELS"
 )]
-pub(crate) async fn list_template_channels(ctx: Context<'_>) -> CommandResult {
-    let span = trace_span!("list_template_channels span");
+pub(crate) async fn set_status_template(
+    ctx: Context<'_>,
+    #[description = "The ID of the channel to alter"] channel_id: ChannelId,
+    #[description = "The template to use. Leave out to stop setting a status"] template: Option<
+        String,
+    >,
+) -> CommandResult {
+    let span = trace_span!("set_status_template span");
     async move {
         let guild_id = ctx.guild().unwrap().id;
-        let all_channels = super::db::get_all_channels_in_guild(
+        let template = match template
+            .as_deref()
+            .map(super::parser::parse_template)
+            .transpose()
+        {
+            | Ok(template) => template,
+            | Err(err) => return reply_with_template_error(ctx, &err).await,
+        };
+        super::db::set_status_template(
             &get_db_handle(ctx.serenity_context()).await,
             guild_id,
+            channel_id,
+            template.as_ref(),
         )
         .await
-        .wrap_err_with(|| {
-            eyre!("Failed to get all channels in guild in list_template_channels!")
-        })?;
-        let mut message = format!("{}:\n`", ctx.author().mention(),);
-        for (parent_number, (parent, children)) in (1..=all_channels.len()).zip(&all_channels) {
-            let channel = ctx.cache().guild_channel(guild_id, parent.id)?;
-            let parent_name = channel.name();
-            writeln!(message, "\tParent {parent_number}: \"{parent_name}\"")
-                .wrap_err_with(|| eyre!("Failed to write parent name to message!"))?;
+        .wrap_err_with(|| eyre!("Failed to set status template!"))?;
+        get_template_cache(ctx.serenity_context())
+            .await
+            .evict_parents(guild_id, [channel_id])
+            .await;
+
+        if template.is_none() {
+            clear_child_statuses(ctx.serenity_context(), guild_id, &Parent::from(channel_id))
+                .await
+                .wrap_err_with(|| eyre!("Failed to clear statuses of children!"))?;
+        }
+        update_children(ctx.serenity_context(), guild_id, &Parent::from(channel_id))
+            .await
+            .wrap_err_with(|| eyre!("Failed to update children after setting status template!"))?;
+
+        let locale = guild_locale(ctx).await?;
+        let message = match &template {
+            | Some(template) => Reply::StatusTemplateChanged {
+                template: &template.to_string(),
+            }
+            .localize(locale),
+            | None => Reply::StatusTemplateRemoved.localize(locale),
+        };
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!("{} - {message}", ctx.author().mention()),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!("Set status template for channel with ID {channel_id}!");
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+/// Sets an expression giving the bitrate in kbps of generated channels.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn set_bitrate(
+    ctx: Context<'_>,
+    #[description = "The ID of the channel to alter"] channel_id: ChannelId,
+    #[description = "The bitrate in kbps, e.g. `64` or `32 * users`. Leave out to stop setting it"]
+    expression: Option<String>,
+) -> CommandResult {
+    let span = trace_span!("set_bitrate span");
+    async move {
+        let guild_id = ctx.guild().unwrap().id;
+        let expression = match expression
+            .as_deref()
+            .map(super::parser::parse_expression)
+            .transpose()
+        {
+            | Ok(expression) => expression,
+            | Err(err) => return reply_with_template_error(ctx, &err).await,
+        };
+        super::db::set_bitrate_template(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            channel_id,
+            expression.as_ref(),
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to set bitrate template!"))?;
+        get_template_cache(ctx.serenity_context())
+            .await
+            .evict_parents(guild_id, [channel_id])
+            .await;
+
+        update_children(ctx.serenity_context(), guild_id, &Parent::from(channel_id))
+            .await
+            .wrap_err_with(|| eyre!("Failed to update children after setting bitrate!"))?;
+
+        let locale = guild_locale(ctx).await?;
+        let message = match &expression {
+            | Some(expression) => Reply::BitrateChanged {
+                expression: &expression.to_string(),
+            }
+            .localize(locale),
+            | None => Reply::BitrateReset.localize(locale),
+        };
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!("{} - {message}", ctx.author().mention()),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!("Set bitrate template for channel with ID {channel_id}!");
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+/// Sets an expression giving the user limit of generated channels.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn set_user_limit(
+    ctx: Context<'_>,
+    #[description = "The ID of the channel to alter"] channel_id: ChannelId,
+    #[description = "The user limit, e.g. `users + 1`. Leave out to use the capacity again"]
+    expression: Option<String>,
+) -> CommandResult {
+    let span = trace_span!("set_user_limit span");
+    async move {
+        let guild_id = ctx.guild().unwrap().id;
+        let expression = match expression
+            .as_deref()
+            .map(super::parser::parse_expression)
+            .transpose()
+        {
+            | Ok(expression) => expression,
+            | Err(err) => return reply_with_template_error(ctx, &err).await,
+        };
+        super::db::set_user_limit_template(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            channel_id,
+            expression.as_ref(),
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to set user limit template!"))?;
+        get_template_cache(ctx.serenity_context())
+            .await
+            .evict_parents(guild_id, [channel_id])
+            .await;
+
+        update_children(ctx.serenity_context(), guild_id, &Parent::from(channel_id))
+            .await
+            .wrap_err_with(|| eyre!("Failed to update children after setting user limit!"))?;
+
+        let locale = guild_locale(ctx).await?;
+        let message = match &expression {
+            | Some(expression) => Reply::UserLimitChanged {
+                expression: &expression.to_string(),
+            }
+            .localize(locale),
+            | None => Reply::UserLimitReset.localize(locale),
+        };
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!("{} - {message}", ctx.author().mention()),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!("Set user limit template for channel with ID {channel_id}!");
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+/// Sets how generated channels are numbered.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn set_numbering(
+    ctx: Context<'_>,
+    #[description = "The ID of the channel to alter"] channel_id: ChannelId,
+    #[description = "How to number new channels"] strategy: NumberingStrategy,
+) -> CommandResult {
+    let span = trace_span!("set_numbering span");
+    async move {
+        let guild_id = ctx.guild().unwrap().id;
+        let db_handle = get_db_handle(ctx.serenity_context()).await;
+        super::db::set_numbering(&db_handle, guild_id, channel_id, strategy)
+            .await
+            .wrap_err_with(|| eyre!("Failed to set numbering strategy!"))?;
+
+        // Existing gaps are closed right away rather than on the next deletion.
+        if strategy == NumberingStrategy::Compact {
+            if let Some(parent) = get_parent(ctx, guild_id, channel_id).await? {
+                compact_numbering_scope(ctx, guild_id, &parent).await?;
+            }
+        }
+
+        let locale = guild_locale(ctx).await?;
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!(
+                    "{} - {}",
+                    ctx.author().mention(),
+                    Reply::NumberingChanged {
+                        strategy: strategy.name(),
+                    }
+                    .localize(locale)
+                ),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!("Set numbering strategy for channel with ID {channel_id}!");
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+/// Makes generated channels share their numbers with those of other parents in
+/// the same group.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn set_numbering_group(
+    ctx: Context<'_>,
+    #[description = "The ID of the channel to alter"] channel_id: ChannelId,
+    #[description = "The name of the group, e.g. `lobbies`. Leave out to leave the current group"]
+    group: Option<String>,
+) -> CommandResult {
+    let span = trace_span!("set_numbering_group span");
+    async move {
+        let guild_id = ctx.guild().unwrap().id;
+        let locale = guild_locale(ctx).await?;
+        if let Some(problem) = group.as_deref().and_then(validate_name) {
+            ctx.channel_id()
+                .say(
+                    &ctx.http(),
+                    format!("{}: {}", ctx.author().mention(), problem.localize(locale)),
+                )
+                .await
+                .wrap_err_with(|| eyre!("Failed to send message!"))?
+                .drop();
+            return Ok(());
+        }
+        let old_parent = get_parent(ctx, guild_id, channel_id).await?;
+        super::db::set_numbering_group(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            channel_id,
+            group.as_deref(),
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to set numbering group!"))?;
+
+        // Both the group that was left and the one that was joined may need
+        // their gaps closed.
+        if let Some(old_parent) = old_parent {
+            if old_parent.numbering == NumberingStrategy::Compact {
+                let new_parent = Parent {
+                    numbering_group: group.clone(),
+                    ..old_parent.clone()
+                };
+                compact_numbering_scope(ctx, guild_id, &old_parent).await?;
+                compact_numbering_scope(ctx, guild_id, &new_parent).await?;
+            }
+        }
+
+        let message = match &group {
+            | Some(group) => Reply::NumberingGroupChanged { group }.localize(locale),
+            | None => Reply::NumberingGroupLeft.localize(locale),
+        };
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!("{} - {message}", ctx.author().mention()),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!("Set numbering group for channel with ID {channel_id}!");
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+
+/// Sets the number the first generated channel gets.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn set_first_number(
+    ctx: Context<'_>,
+    #[description = "The ID of the channel to alter"] channel_id: ChannelId,
+    #[description = "The number of the first channel, e.g. `0` or `100`"]
+    #[max = 1_000_000]
+    number: u64,
+) -> CommandResult {
+    let span = trace_span!("set_first_number span");
+    async move {
+        let guild_id = ctx.guild().unwrap().id;
+        super::db::set_first_child_number(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            channel_id,
+            number,
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to set first child number!"))?;
+
+        // Compact numbering moves existing children to the new start right
+        // away, the other strategies only affect new children.
+        if let Some(parent) = get_parent(ctx, guild_id, channel_id).await? {
+            if parent.numbering == NumberingStrategy::Compact {
+                compact_numbering_scope(ctx, guild_id, &parent).await?;
+            }
+        }
+
+        let locale = guild_locale(ctx).await?;
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!(
+                    "{} - {}",
+                    ctx.author().mention(),
+                    Reply::FirstNumberChanged { number }.localize(locale)
+                ),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!("Set first child number for channel with ID {channel_id} to {number}!");
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+/// Limits how many generated channels a template channel may have at once.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn set_max_children(
+    ctx: Context<'_>,
+    #[description = "The ID of the channel to alter"] channel_id: ChannelId,
+    #[description = "The maximum number of channels. Leave out to remove the limit"]
+    #[min = 1]
+    max: Option<u64>,
+) -> CommandResult {
+    let span = trace_span!("set_max_children span");
+    async move {
+        let guild_id = ctx.guild().unwrap().id;
+        super::db::set_max_children(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            channel_id,
+            max,
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to set maximum children!"))?;
+
+        let locale = guild_locale(ctx).await?;
+        let message = match max {
+            | Some(max) => Reply::MaxChildrenChanged { max }.localize(locale),
+            | None => Reply::MaxChildrenRemoved.localize(locale),
+        };
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!("{} - {message}", ctx.author().mention()),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!("Set maximum children for channel with ID {channel_id}!");
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+
+/// Sets how long generated channels of a template channel are kept around after
+/// everyone has left them.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn set_grace_period(
+    ctx: Context<'_>,
+    #[description = "The ID of the channel to alter"] channel_id: ChannelId,
+    #[description = "How many seconds to wait. Leave out to delete empty channels right away"]
+    #[max = 3600]
+    seconds: Option<u64>,
+) -> CommandResult {
+    let span = trace_span!("set_grace_period span");
+    async move {
+        let guild_id = ctx.guild().unwrap().id;
+        super::db::set_grace_period(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            channel_id,
+            Duration::from_secs(seconds.unwrap_or_default()),
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to set grace period!"))?;
+
+        let locale = guild_locale(ctx).await?;
+        let message = match seconds.filter(|&seconds| seconds > 0) {
+            | Some(seconds) => Reply::GracePeriodChanged { seconds }.localize(locale),
+            | None => Reply::GracePeriodRemoved.localize(locale),
+        };
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!("{} - {message}", ctx.author().mention()),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!("Set grace period for channel with ID {channel_id}!");
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+
+/// Sets what happens to a generated channel when its owner leaves while others
+/// stay.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn set_ownership_transfer(
+    ctx: Context<'_>,
+    #[description = "The ID of the channel to alter"] channel_id: ChannelId,
+    #[description = "What happens when the owner leaves"] transfer: OwnershipTransfer,
+) -> CommandResult {
+    let span = trace_span!("set_ownership_transfer span");
+    async move {
+        let guild_id = ctx.guild().unwrap().id;
+        super::db::set_ownership_transfer(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            channel_id,
+            transfer,
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to set ownership transfer!"))?;
+
+        let locale = guild_locale(ctx).await?;
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!(
+                    "{} - {}",
+                    ctx.author().mention(),
+                    Reply::OwnershipTransferChanged {
+                        transfer: transfer.name(),
+                    }
+                    .localize(locale)
+                ),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!("Set ownership transfer for channel with ID {channel_id}!");
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+
+/// Sets whether channels generated from a parent copy one of its settings.
+///
+/// Of the inherited settings, owners can only change the bitrate and the
+/// permissions of their own channel, with `vc bitrate` and the access commands.
+/// The voice region, video quality and age restriction can't be changed per
+/// channel.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn set_inheritance(
+    ctx: Context<'_>,
+    #[description = "The ID of the channel to alter"] channel_id: ChannelId,
+    #[description = "The setting of the channel"] setting: ChildSetting,
+    #[description = "Whether generated channels copy the setting"] inherit: bool,
+) -> CommandResult {
+    let span = trace_span!("set_inheritance span");
+    async move {
+        let guild_id = ctx.guild().unwrap().id;
+        super::db::set_inherited(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            channel_id,
+            setting,
+            inherit,
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to set inheritance!"))?;
+
+        let locale = guild_locale(ctx).await?;
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!(
+                    "{} - {}",
+                    ctx.author().mention(),
+                    Reply::InheritanceChanged {
+                        setting: setting.name(),
+                        inherit,
+                    }
+                    .localize(locale)
+                ),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!(
+            "Set inheritance of {} for channel with ID {channel_id}!",
+            setting.name()
+        );
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+
+/// Looks up a parent by its ID, returning `None` if the channel isn't a parent.
+async fn get_parent(
+    ctx: Context<'_>,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Result<Option<Parent>> {
+    Ok(super::db::get_all_children_of_parent(
+        &get_db_handle(ctx.serenity_context()).await,
+        &*get_template_cache(ctx.serenity_context()).await,
+        guild_id,
+        &[channel_id.get() as i64],
+    )
+    .await
+    .wrap_err_with(|| eyre!("Retrieving voice channels failed!"))?
+    .map(|(parent, _)| parent)
+    .filter(|parent| parent.id == channel_id))
+}
+
+/// Renumbers the children sharing numbers with a parent to 1..N and renames
+/// them.
+async fn compact_numbering_scope(
+    ctx: Context<'_>,
+    guild_id: GuildId,
+    parent: &Parent,
+) -> Result<()> {
+    super::db::compact_child_numbers(
+        &get_db_handle(ctx.serenity_context()).await,
+        guild_id,
+        parent,
+    )
+    .await
+    .wrap_err_with(|| eyre!("Failed to compact child numbers!"))?;
+    update_numbering_scope(ctx.serenity_context(), guild_id, parent)
+        .await
+        .wrap_err_with(|| eyre!("Failed to update children after compacting!"))
+}
+
+/// Suggests time zones matching what has been typed so far.
+#[allow(clippy::unused_async)]
+async fn autocomplete_time_zone(_ctx: Context<'_>, partial: &str) -> Vec<&'static str> {
+    let partial = partial.to_lowercase();
+    TZ_VARIANTS
+        .iter()
+        .map(|time_zone| time_zone.name())
+        .filter(|name| name.to_lowercase().contains(&partial))
+        // Discord shows at most 25 suggestions.
+        .take(25)
+        .collect()
+}
+
+/// Sets the time zone that time directives such as `{time}` are rendered in.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    aliases("set_tz"),
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn set_time_zone(
+    ctx: Context<'_>,
+    #[description = "The IANA name of the time zone, e.g. `Europe/Stockholm`."]
+    #[autocomplete = "autocomplete_time_zone"]
+    time_zone: String,
+) -> CommandResult {
+    let span = trace_span!("set_time_zone span");
+    async move {
+        let guild_id = ctx.guild_id().unwrap();
+        let locale = guild_locale(ctx).await?;
+        let Ok(parsed_time_zone) = time_zone.parse::<Tz>() else {
+            ctx.channel_id()
+                .say(
+                    &ctx.http(),
+                    format!(
+                        "{}: {}",
+                        ctx.author().mention(),
+                        Reply::UnknownTimeZone {
+                            time_zone: &time_zone,
+                        }
+                        .localize(locale)
+                    ),
+                )
+                .await
+                .wrap_err_with(|| eyre!("Failed to send message!"))?
+                .drop();
+            return Ok(());
+        };
+
+        store_time_zone(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            parsed_time_zone,
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed at setting time zone!"))?;
+
+        update_time_dependent_children(ctx.serenity_context(), guild_id)
+            .await
+            .wrap_err_with(|| eyre!("Failed to update children after setting time zone!"))?;
+
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!(
+                    "{} - {}",
+                    ctx.author().mention(),
+                    Reply::TimeZoneChanged {
+                        time_zone: parsed_time_zone.name(),
+                    }
+                    .localize(locale)
+                ),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!("Set time zone for guild with ID {guild_id} to {parsed_time_zone}!");
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+
+/// Sets the language number words, ordinals and bot replies use in your guild.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn set_locale(
+    ctx: Context<'_>,
+    #[description = "The language to use."] locale: Locale,
+) -> CommandResult {
+    let span = trace_span!("set_locale span");
+    async move {
+        let guild_id = ctx.guild_id().unwrap();
+        store_locale(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            locale,
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed at setting locale!"))?;
+
+        update_locale_dependent_children(ctx.serenity_context(), guild_id)
+            .await
+            .wrap_err_with(|| eyre!("Failed to update children after setting locale!"))?;
+
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!(
+                    "{} - {}",
+                    ctx.author().mention(),
+                    Reply::LocaleChanged.localize(locale)
+                ),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!(
+            "Set locale for guild with ID {guild_id} to {}!",
+            locale.code()
+        );
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+/// Checks that a variable or name pool name can be referenced from a
+/// template, returning why it can't otherwise.
+fn validate_name(name: &str) -> Option<Reply<'static>> {
+    if name.is_empty() {
+        Some(Reply::NameEmpty)
+    } else if name.len() > MAX_NAME_LENGTH {
+        Some(Reply::NameTooLong)
+    } else if !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+        Some(Reply::NameInvalidCharacters)
+    } else {
+        None
+    }
+}
+
+/// Sets a variable that templates in your guild can reference as `{$name}`.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    aliases("set_var"),
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn set_variable(
+    ctx: Context<'_>,
+    #[description = "The name of the variable, e.g. `season`."] name: String,
+    #[description = "The value of the variable, e.g. `S12`."] value: String,
+) -> CommandResult {
+    let span = trace_span!("set_variable span");
+    async move {
+        let guild_id = ctx.guild_id().unwrap();
+        let name = name.strip_prefix('$').unwrap_or(&name);
+        let locale = guild_locale(ctx).await?;
+        let problem = validate_name(name).or_else(|| {
+            if value.chars().count() > MAX_CHANNEL_NAME_LENGTH {
+                Some(Reply::VariableValueTooLong)
+            } else if value.chars().any(char::is_control) {
+                Some(Reply::VariableValueControlCharacters)
+            } else {
+                None
+            }
+        });
+        if let Some(problem) = problem {
+            ctx.channel_id()
+                .say(
+                    &ctx.http(),
+                    format!("{}: {}", ctx.author().mention(), problem.localize(locale)),
+                )
+                .await
+                .wrap_err_with(|| eyre!("Failed to send message!"))?
+                .drop();
+            return Ok(());
+        }
+
+        super::db::set_variable(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            name,
+            &value,
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed at setting variable!"))?;
+
+        update_children_using_variable(ctx.serenity_context(), guild_id, name)
+            .await
+            .wrap_err_with(|| eyre!("Failed to update children after setting variable!"))?;
+
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!(
+                    "{} - {}",
+                    ctx.author().mention(),
+                    Reply::VariableSet {
+                        name,
+                        value: &value,
+                    }
+                    .localize(locale)
+                ),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!("Set variable {name} for guild with ID {guild_id} to {value}!");
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+
+/// Deletes a variable. Templates referencing it will render nothing in its
+/// place.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    aliases("delete_var", "remove_variable", "remove_var"),
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn delete_variable(
+    ctx: Context<'_>,
+    #[description = "The name of the variable to delete."] name: String,
+) -> CommandResult {
+    let span = trace_span!("delete_variable span");
+    async move {
+        let guild_id = ctx.guild_id().unwrap();
+        let name = name.strip_prefix('$').unwrap_or(&name);
+
+        let existed = super::db::delete_variable(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            name,
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed at deleting variable!"))?;
+        if existed {
+            update_children_using_variable(ctx.serenity_context(), guild_id, name)
+                .await
+                .wrap_err_with(|| eyre!("Failed to update children after deleting variable!"))?;
+        }
+
+        let locale = guild_locale(ctx).await?;
+        let message = if existed {
+            format!(
+                "{} - {}",
+                ctx.author().mention(),
+                Reply::VariableDeleted { name }.localize(locale)
+            )
+        } else {
+            format!(
+                "{}: {}",
+                ctx.author().mention(),
+                Reply::NoSuchVariable { name }.localize(locale)
+            )
+        };
+        ctx.channel_id()
+            .say(&ctx.http(), message)
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+
+/// Lists the variables defined in your guild.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    aliases("list_vars"),
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn list_variables(ctx: Context<'_>) -> CommandResult {
+    let span = trace_span!("list_variables span");
+    async move {
+        let guild_id = ctx.guild_id().unwrap();
+        let mut variables =
+            super::db::get_variables(&get_db_handle(ctx.serenity_context()).await, guild_id)
+                .await
+                .wrap_err_with(|| eyre!("Failed to retrieve variables!"))?
+                .into_iter()
+                .collect::<Vec<_>>();
+        variables.sort_unstable();
+
+        let locale = guild_locale(ctx).await?;
+        let mut message = format!("{}: ", ctx.author().mention());
+        if variables.is_empty() {
+            message.push_str(&Reply::NoVariables.localize(locale));
+        } else {
+            writeln!(message, "{}", Reply::Variables.localize(locale))
+                .wrap_err_with(|| eyre!("Failed to write header to message!"))?;
+            for (name, value) in variables {
+                writeln!(message, "`{{${name}}}` = `{value}`")
+                    .wrap_err_with(|| eyre!("Failed to write variable to message!"))?;
+            }
+        }
+        ctx.channel_id()
+            .say(&ctx.http(), message)
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+/// Sets a pool of unique names that templates can use with `{pool:name}`.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    aliases("set_pool"),
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn set_name_pool(
+    ctx: Context<'_>,
+    #[description = "The name of the pool, e.g. `nato`."] name: String,
+    #[description = "The comma separated entries of the pool, e.g. `Alpha, Bravo, Charlie`."]
+    entries: String,
+) -> CommandResult {
+    let span = trace_span!("set_name_pool span");
+    async move {
+        let guild_id = ctx.guild_id().unwrap();
+        let entries = entries
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        let locale = guild_locale(ctx).await?;
+        let problem = validate_name(&name).or_else(|| {
+            let mut seen = HashSet::default();
+            if entries.is_empty() {
+                Some(Reply::NamePoolEmpty)
+            } else if entries.len() > MAX_POOL_ENTRIES {
+                Some(Reply::NamePoolTooLarge)
+            } else if entries
+                .iter()
+                .any(|entry| entry.chars().count() > MAX_CHANNEL_NAME_LENGTH)
+            {
+                Some(Reply::PoolEntryTooLong)
+            } else if entries
+                .iter()
+                .any(|entry| entry.chars().any(char::is_control))
+            {
+                Some(Reply::PoolEntryControlCharacters)
+            } else if !entries.iter().all(|entry| seen.insert(entry)) {
+                Some(Reply::PoolEntriesNotUnique)
+            } else {
+                None
+            }
+        });
+        if let Some(problem) = problem {
+            ctx.channel_id()
+                .say(
+                    &ctx.http(),
+                    format!("{}: {}", ctx.author().mention(), problem.localize(locale)),
+                )
+                .await
+                .wrap_err_with(|| eyre!("Failed to send message!"))?
+                .drop();
+            return Ok(());
+        }
+
+        super::db::set_name_pool(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            &name,
+            &entries,
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed at setting name pool!"))?;
+
+        ctx.channel_id()
+            .say(
+                &ctx.http(),
+                format!(
+                    "{} - {}",
+                    ctx.author().mention(),
+                    Reply::NamePoolSet {
+                        name:    &name,
+                        entries: entries.len(),
+                    }
+                    .localize(locale)
+                ),
+            )
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        info!("Set name pool {name} for guild with ID {guild_id} to {entries:?}!");
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+
+/// Deletes a name pool. Children keep the entries they were already given.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    aliases("delete_pool", "remove_name_pool", "remove_pool"),
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn delete_name_pool(
+    ctx: Context<'_>,
+    #[description = "The name of the pool to delete."] name: String,
+) -> CommandResult {
+    let span = trace_span!("delete_name_pool span");
+    async move {
+        let guild_id = ctx.guild_id().unwrap();
+
+        let existed = super::db::delete_name_pool(
+            &get_db_handle(ctx.serenity_context()).await,
+            guild_id,
+            &name,
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed at deleting name pool!"))?;
+
+        let locale = guild_locale(ctx).await?;
+        let message = if existed {
+            format!(
+                "{} - {}",
+                ctx.author().mention(),
+                Reply::NamePoolDeleted { name: &name }.localize(locale)
+            )
+        } else {
+            format!(
+                "{}: {}",
+                ctx.author().mention(),
+                Reply::NoSuchNamePool { name: &name }.localize(locale)
+            )
+        };
+        ctx.channel_id()
+            .say(&ctx.http(), message)
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+
+/// Lists the name pools defined in your guild.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    aliases("list_pools"),
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn list_name_pools(ctx: Context<'_>) -> CommandResult {
+    let span = trace_span!("list_name_pools span");
+    async move {
+        let guild_id = ctx.guild_id().unwrap();
+        let mut pools =
+            super::db::get_name_pools(&get_db_handle(ctx.serenity_context()).await, guild_id)
+                .await
+                .wrap_err_with(|| eyre!("Failed to retrieve name pools!"))?
+                .into_iter()
+                .collect::<Vec<_>>();
+        pools.sort_unstable();
+
+        let locale = guild_locale(ctx).await?;
+        let mut message = format!("{}: ", ctx.author().mention());
+        if pools.is_empty() {
+            message.push_str(&Reply::NoNamePools.localize(locale));
+        } else {
+            writeln!(message, "{}", Reply::NamePools.localize(locale))
+                .wrap_err_with(|| eyre!("Failed to write header to message!"))?;
+            for (name, entries) in pools {
+                writeln!(message, "`{name}`: {}", entries.join(", "))
+                    .wrap_err_with(|| eyre!("Failed to write name pool to message!"))?;
+            }
+        }
+        ctx.channel_id()
+            .say(&ctx.http(), message)
+            .await
+            .wrap_err_with(|| eyre!("Failed to send message!"))?
+            .drop();
+        Ok(())
+    }
+    .instrument(span)
+    .await
+}
+/// Lists all template channels and their children in your guild.
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    aliases(
+        "list_template",
+        "list_templates",
+        "list_template_channel",
+        "list",
+        "list_channel",
+        "list_channels"
+    ),
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub(crate) async fn list_template_channels(ctx: Context<'_>) -> CommandResult {
+    let span = trace_span!("list_template_channels span");
+    async move {
+        let guild_id = ctx.guild().unwrap().id;
+        let all_channels = super::db::get_all_channels_in_guild(
+            &get_db_handle(ctx.serenity_context()).await,
+            &*get_template_cache(ctx.serenity_context()).await,
+            guild_id,
+        )
+        .await
+        .wrap_err_with(|| {
+            eyre!("Failed to get all channels in guild in list_template_channels!")
+        })?;
+        let locale = guild_locale(ctx).await?;
+        let mut message = format!("{}:\n`", ctx.author().mention());
+        for (parent_number, (parent, children)) in (1..=all_channels.len()).zip(&all_channels) {
+            let channel = ctx.cache().guild_channel(guild_id, parent.id)?;
+            let parent_name = channel.name();
+            writeln!(
+                message,
+                "\t{}",
+                Reply::ListedParent {
+                    number:   parent_number,
+                    name:     parent_name,
+                    template: &parent.templates.name.to_string(),
+                }
+                .localize(locale)
+            )
+            .wrap_err_with(|| eyre!("Failed to write parent name to message!"))?;
             for child in children {
                 let channel = ctx.cache().guild_channel(guild_id, parent.id)?;
                 let child_number = child.number;
                 let child_name = channel.name();
-                writeln!(message, "\t\tChild {child_number}: \"{child_name}\"")
-                    .wrap_err_with(|| eyre!("Failed to write child name to message!"))?;
+                writeln!(
+                    message,
+                    "\t\t{}",
+                    Reply::ListedChild {
+                        number: child_number,
+                        name:   child_name,
+                    }
+                    .localize(locale)
+                )
+                .wrap_err_with(|| eyre!("Failed to write child name to message!"))?;
             }
         }
         message.push('`');
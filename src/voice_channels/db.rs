@@ -1,5 +1,9 @@
 use core::hash::Hash;
-use std::hash::Hasher;
+use std::{
+    hash::Hasher,
+    sync::Arc,
+    time::Duration,
+};
 
 use eyre::{
     eyre,
@@ -7,11 +11,15 @@ use eyre::{
     WrapErr,
 };
 use if_chain::if_chain;
+use poise::ChoiceParameter;
 use serenity::model::prelude::*;
 use sqlx::{
     query,
+    types::Json,
+    PgConnection,
     PgPool,
 };
+use tokio::sync::RwLock;
 #[allow(unused_imports)]
 use tracing::{
     debug,
@@ -21,12 +29,148 @@ use tracing::{
     warn,
 };
 
+use super::{
+    expr::Expr,
+    parser::Template,
+    updater::{
+        ChannelOverrides,
+        ChannelTemplates,
+        RenderState,
+    },
+};
 use crate::{
     DropExt,
     HashMap,
     HashSet,
 };
 
+/// The parsed templates of every parent, so that they are parsed once when the
+/// parent is first loaded rather than on every voice event. Entries have to be
+/// evicted whenever the templates of a parent change or the parent is removed.
+#[derive(Debug, Default)]
+pub(crate) struct TemplateCache {
+    inner: RwLock<TemplateCacheInner>,
+}
+
+#[derive(Debug, Default)]
+struct TemplateCacheInner {
+    /// Bumped on every eviction, so that templates loaded from the database
+    /// before an eviction aren't cached after it.
+    generation: u64,
+    guilds:     HashMap<GuildId, HashMap<ChannelId, Arc<ChannelTemplates>>>,
+}
+
+impl TemplateCache {
+    async fn get(&self, guild_id: GuildId, parent_id: ChannelId) -> Option<Arc<ChannelTemplates>> {
+        self.inner
+            .read()
+            .await
+            .guilds
+            .get(&guild_id)?
+            .get(&parent_id)
+            .cloned()
+    }
+
+    async fn generation(&self) -> u64 {
+        self.inner.read().await.generation
+    }
+
+    /// Caches the templates of a parent, unless an entry was evicted since
+    /// `generation` was read.
+    async fn insert(
+        &self,
+        generation: u64,
+        guild_id: GuildId,
+        parent_id: ChannelId,
+        templates: Arc<ChannelTemplates>,
+    ) {
+        let mut inner = self.inner.write().await;
+        if inner.generation == generation {
+            inner
+                .guilds
+                .entry(guild_id)
+                .or_default()
+                .insert(parent_id, templates)
+                .drop();
+        }
+    }
+
+    /// Evicts the templates of the given parents.
+    pub(crate) async fn evict_parents(
+        &self,
+        guild_id: GuildId,
+        parent_ids: impl IntoIterator<Item = ChannelId>,
+    ) {
+        let mut inner = self.inner.write().await;
+        inner.generation += 1;
+        if let Some(parents) = inner.guilds.get_mut(&guild_id) {
+            for parent_id in parent_ids {
+                parents.remove(&parent_id).drop();
+            }
+        }
+    }
+
+    /// Evicts the templates of every parent in a guild.
+    pub(crate) async fn evict_guild(&self, guild_id: GuildId) {
+        let mut inner = self.inner.write().await;
+        inner.generation += 1;
+        inner.guilds.remove(&guild_id).drop();
+    }
+
+    /// Evicts the templates of every parent outside the given guilds.
+    pub(crate) async fn retain_guilds(&self, guild_ids: &[GuildId]) {
+        let mut inner = self.inner.write().await;
+        inner.generation += 1;
+        inner
+            .guilds
+            .retain(|guild_id, _| guild_ids.contains(guild_id));
+    }
+}
+
+/// Returns the parsed templates of a parent, parsing them if they aren't
+/// cached yet. Returns `None` if the parent doesn't exist, or if its templates
+/// no longer parse, which only disables that parent.
+async fn templates_of(
+    executor: &PgPool,
+    cache: &TemplateCache,
+    guild_id: GuildId,
+    parent_id: ChannelId,
+) -> Result<Option<Arc<ChannelTemplates>>> {
+    if let Some(templates) = cache.get(guild_id, parent_id).await {
+        return Ok(Some(templates));
+    }
+    let generation = cache.generation().await;
+    let Some(row) = query!(
+        "SELECT channel_template, status_template, bitrate_template, user_limit_template FROM \
+         template_channels WHERE channel_id = $1;",
+        parent_id.get() as i64
+    )
+    .fetch_optional(executor)
+    .await
+    .wrap_err_with(|| eyre!("Getting templates of parent {parent_id} failed!"))?
+    else {
+        return Ok(None);
+    };
+    match ChannelTemplates::parse(
+        &row.channel_template,
+        row.status_template.as_deref(),
+        row.bitrate_template.as_deref(),
+        row.user_limit_template.as_deref(),
+    ) {
+        | Ok(templates) => {
+            let templates = Arc::new(templates);
+            cache
+                .insert(generation, guild_id, parent_id, Arc::clone(&templates))
+                .await;
+            Ok(Some(templates))
+        },
+        | Err(err) => {
+            error!("Skipping parent {parent_id}, whose templates failed to parse: {err}");
+            Ok(None)
+        },
+    }
+}
+
 #[allow(dead_code)]
 pub(crate) async fn get_template(executor: &PgPool, guild_id: GuildId) -> Result<Option<String>> {
     query!(
@@ -39,11 +183,13 @@ pub(crate) async fn get_template(executor: &PgPool, guild_id: GuildId) -> Result
     .map(|row| row.map(|row| row.channel_template))
 }
 
+/// Stores the canonical form of a template, so that equivalent templates are
+/// stored the same way.
 pub(crate) async fn set_template(
     executor: &PgPool,
     channel_id: ChannelId,
     guild_id: GuildId,
-    template: String,
+    template: &Template,
 ) -> Result<()> {
     query!(
         "INSERT INTO template_channels (channel_id, guild_id, channel_template, \
@@ -51,7 +197,7 @@ pub(crate) async fn set_template(
          channel_template = $3;",
         channel_id.get() as i64,
         guild_id.get() as i64,
-        template
+        template.to_string()
     )
     .execute(executor)
     .await
@@ -99,12 +245,178 @@ pub(crate) async fn delete_template(
     Ok(())
 }
 
+/// Allocates a number for a new child of a parent according to its numbering
+/// strategy and first number, among the children of every parent in its
+/// numbering group.
+async fn allocate_child_number(
+    connection: &mut PgConnection,
+    guild_id: GuildId,
+    parent_id: ChannelId,
+) -> Result<i64> {
+    let parent_row = query!(
+        "SELECT numbering, numbering_group, first_child_number FROM template_channels WHERE \
+         channel_id = $1 AND guild_id = $2;",
+        parent_id.get() as i64,
+        guild_id.get() as i64
+    )
+    .fetch_one(&mut *connection)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Getting parent from database for server with id {guild_id} failed!")
+    })?;
+
+    let next_child_number = lock_numbering_scope(
+        connection,
+        guild_id,
+        parent_id,
+        parent_row.numbering_group.as_deref(),
+    )
+    .await?;
+    let taken_numbers = numbered_children(
+        connection,
+        guild_id,
+        parent_id,
+        parent_row.numbering_group.as_deref(),
+    )
+    .await?
+    .into_iter()
+    .map(|(_, number)| number)
+    .collect::<Vec<_>>();
+    let child_number = pick_child_number(
+        parse_numbering(&parent_row.numbering)?,
+        parent_row.first_child_number,
+        next_child_number,
+        &taken_numbers,
+    );
+
+    query!(
+        "UPDATE template_channels SET next_child_number = $4 WHERE channel_id = $1 OR (guild_id = \
+         $2 AND numbering_group = $3);",
+        parent_id.get() as i64,
+        guild_id.get() as i64,
+        parent_row.numbering_group,
+        child_number + 1
+    )
+    .execute(&mut *connection)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Updating next child number in database for server with id {guild_id} failed!")
+    })?
+    .drop();
+
+    Ok(child_number)
+}
+
+/// Locks every parent sharing the number space of a parent until the
+/// transaction ends, which keeps siblings from being allocated the same number
+/// or pool entry, or from being renumbered, concurrently. Returns the highest
+/// next number of a monotonic counter among them.
+async fn lock_numbering_scope(
+    connection: &mut PgConnection,
+    guild_id: GuildId,
+    parent_id: ChannelId,
+    numbering_group: Option<&str>,
+) -> Result<i64> {
+    // The parents are locked in a fixed order, so that two transactions
+    // locking the same group can't deadlock.
+    query!(
+        r#"SELECT MAX(next_child_number) AS "next_child_number!" FROM (SELECT next_child_number FROM template_channels WHERE channel_id = $1 OR (guild_id = $2 AND numbering_group = $3) ORDER BY channel_id FOR UPDATE) AS scope;"#,
+        parent_id.get() as i64,
+        guild_id.get() as i64,
+        numbering_group
+    )
+    .fetch_one(&mut *connection)
+    .await
+    .wrap_err_with(|| eyre!("Locking parents in database for server with id {guild_id} failed!"))
+    .map(|row| row.next_child_number)
+}
+
+/// Returns the IDs and numbers of the children sharing the number space of a
+/// parent.
+async fn numbered_children(
+    connection: &mut PgConnection,
+    guild_id: GuildId,
+    parent_id: ChannelId,
+    numbering_group: Option<&str>,
+) -> Result<Vec<(i64, i64)>> {
+    query!(
+        "SELECT child_id, child_number FROM child_channels WHERE parent_id IN (SELECT channel_id \
+         FROM template_channels WHERE channel_id = $1 OR (guild_id = $2 AND numbering_group = \
+         $3));",
+        parent_id.get() as i64,
+        guild_id.get() as i64,
+        numbering_group
+    )
+    .fetch_all(&mut *connection)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Getting child numbers from database for server with id {guild_id} failed!")
+    })
+    .map(|rows| {
+        rows.into_iter()
+            .map(|row| (row.child_id, row.child_number))
+            .collect()
+    })
+}
+
+/// Picks the number of a new child, given the numbers its siblings already
+/// have and the next number of the monotonic counter of its number space.
+fn pick_child_number(
+    numbering: NumberingStrategy,
+    first_number: i64,
+    next_number: i64,
+    taken_numbers: &[i64],
+) -> i64 {
+    match numbering {
+        | NumberingStrategy::Monotonic => taken_numbers
+            .iter()
+            .map(|number| number + 1)
+            .chain([first_number, next_number])
+            .max()
+            .unwrap_or(first_number),
+        // N siblings leave at least one of first..=first + N free. Compaction
+        // keeps the numbers at first..first + N - 1, so the lowest free number
+        // is first + N unless the group mixes strategies.
+        | NumberingStrategy::LowestFree | NumberingStrategy::Compact => (first_number
+            ..=first_number + taken_numbers.len() as i64)
+            .find(|number| !taken_numbers.contains(number))
+            .unwrap_or(first_number),
+    }
+}
+
+/// Renumbers children, given as their IDs and numbers, to consecutive numbers
+/// starting at the first number, keeping their order. Returns the children
+/// whose number changes along with their new number.
+fn compact_numbers(first_number: i64, mut children: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    children.sort_unstable_by_key(|&(child_id, number)| (number, child_id));
+    children
+        .into_iter()
+        .zip(first_number..)
+        .filter(|&((_, number), new_number)| number != new_number)
+        .map(|((child_id, _), new_number)| (child_id, new_number))
+        .collect()
+}
+
+/// Returns true if a parent with the given number of children can't have
+/// another one.
+pub(crate) fn is_at_max_children(children_count: u64, max_children: Option<u64>) -> bool {
+    max_children.is_some_and(|max_children| children_count >= max_children)
+}
+
+/// Registers a new child, allocating its number according to the numbering
+/// strategy of its parent and, if its template uses a name pool, the first
+/// entry of that pool no child in the guild is using. Returns the number and
+/// the pool entry, which is `None` if the pool is exhausted or doesn't exist,
+/// or `None` if the parent already has as many children as it may have.
 pub(crate) async fn register_child(
     executor: &PgPool,
     guild_id: GuildId,
     parent_id: ChannelId,
     child_id: ChannelId,
-) -> Result<u64> {
+    owner_id: UserId,
+    pool_name: Option<&str>,
+    render_state: &RenderState,
+) -> Result<Option<(u64, Option<String>)>> {
     let mut transaction = executor
         .begin()
         .await
@@ -112,29 +424,76 @@ pub(crate) async fn register_child(
 
     info!("Parent ID {parent_id}, Guild ID: {guild_id}, Child ID: {child_id}");
 
-    let row = query!(
-        "UPDATE template_channels SET next_child_number = next_child_number + 1 WHERE channel_id \
-         = $1 AND guild_id = $2 RETURNING next_child_number - 1 AS child_number;",
-        parent_id.get() as i64,
-        guild_id.get() as i64
+    let child_number = allocate_child_number(&mut transaction, guild_id, parent_id).await?;
+    info!("Allocated child number {child_number}!");
+
+    // The parent is locked now, so no concurrent join can add a child between
+    // counting the children and registering the new one.
+    let limit_row = query!(
+        r#"SELECT (SELECT COUNT(*) FROM child_channels WHERE parent_id = $1) AS "children_count!", max_children FROM template_channels WHERE channel_id = $1;"#,
+        parent_id.get() as i64
     )
     .fetch_one(&mut *transaction)
     .await
     .wrap_err_with(|| {
-        eyre!("Updating next child number in database for server with id {guild_id} failed!")
+        eyre!("Counting children in database for server with id {guild_id} failed!")
     })?;
+    if is_at_max_children(
+        limit_row.children_count as u64,
+        limit_row.max_children.map(|v| v as u64),
+    ) {
+        return Ok(None);
+    }
+
+    let pool_entry = match pool_name {
+        | Some(pool_name) => {
+            // Pools are shared by every parent in the guild, so the pool itself
+            // is locked until the transaction ends, keeping parents outside the
+            // numbering group from being allocated the same entry concurrently.
+            query!(
+                "SELECT guild_id FROM name_pools WHERE guild_id = $1 AND pool_name = $2 FOR \
+                 UPDATE;",
+                guild_id.get() as i64,
+                pool_name
+            )
+            .fetch_optional(&mut *transaction)
+            .await
+            .wrap_err_with(|| {
+                eyre!("Locking pool `{pool_name}` for server with id {guild_id} failed!")
+            })?
+            .drop();
+            query!(
+                "SELECT entry FROM name_pools, UNNEST(entries) WITH ORDINALITY AS pool(entry, \
+                 idx) WHERE guild_id = $1 AND pool_name = $2 AND entry NOT IN (SELECT pool_entry \
+                 FROM child_channels WHERE guild_id = $1 AND pool_name = $2 AND pool_entry IS NOT \
+                 NULL) ORDER BY idx LIMIT 1;",
+                guild_id.get() as i64,
+                pool_name
+            )
+            .fetch_optional(&mut *transaction)
+            .await
+            .wrap_err_with(|| {
+                eyre!(
+                    "Allocating entry of pool `{pool_name}` for server with id {guild_id} failed!"
+                )
+            })?
+            .and_then(|row| row.entry)
+        },
+        | None => None,
+    };
 
-    info!(
-        "Successfully updated next_child_number!: Result: {:?}",
-        row.child_number
-    );
     query!(
-        "INSERT INTO child_channels (guild_id, parent_id, child_id, child_number) VALUES ($1, $2, \
-         $3, $4) ON CONFLICT (child_id) DO NOTHING;",
+        "INSERT INTO child_channels (guild_id, parent_id, child_id, child_number, owner_id, \
+         pool_name, pool_entry, render_state) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT \
+         (child_id) DO NOTHING;",
         guild_id.get() as i64,
         parent_id.get() as i64,
         child_id.get() as i64,
-        row.child_number
+        child_number,
+        owner_id.get() as i64,
+        pool_name,
+        pool_entry,
+        Json(render_state) as _
     )
     .execute(&mut *transaction)
     .await
@@ -143,16 +502,47 @@ pub(crate) async fn register_child(
     })
     .map(|_| ())?;
 
-    let child_number = row
-        .child_number
-        .ok_or_else(|| eyre!("Child number not present!"))?;
-
     transaction
         .commit()
         .await
         .wrap_err_with(|| eyre!("Failed to commit transaction!"))?;
 
-    Ok(child_number as u64)
+    Ok(Some((child_number as u64, pool_entry)))
+}
+
+/// Stores the render state of a child after it has been reconciled with an
+/// altered template.
+pub(crate) async fn set_render_state(
+    executor: &PgPool,
+    child_id: ChannelId,
+    render_state: &RenderState,
+) -> Result<()> {
+    query!(
+        "UPDATE child_channels SET render_state = $2 WHERE child_id = $1;",
+        child_id.get() as i64,
+        Json(render_state) as _
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| eyre!("Storing render state of child with id {child_id} failed!"))
+    .map(|_| ())
+}
+
+/// Stores the properties the owner of a child has set by hand.
+pub(crate) async fn set_child_overrides(
+    executor: &PgPool,
+    child_id: ChannelId,
+    overrides: &ChannelOverrides,
+) -> Result<()> {
+    query!(
+        "UPDATE child_channels SET overrides = $2 WHERE child_id = $1;",
+        child_id.get() as i64,
+        Json(overrides) as _
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| eyre!("Storing overrides of child with id {child_id} failed!"))
+    .map(|_| ())
 }
 
 #[allow(dead_code)]
@@ -210,12 +600,454 @@ pub(crate) async fn clear_capacity(
     .map(|_| ())
 }
 
+pub(crate) async fn set_status_template(
+    executor: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    template: Option<&Template>,
+) -> Result<()> {
+    query!(
+        "UPDATE template_channels SET status_template = $3 WHERE guild_id = $1 AND channel_id = \
+         $2;",
+        guild_id.get() as i64,
+        channel_id.get() as i64,
+        template.map(ToString::to_string)
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Setting status template in database for server with id {guild_id} failed!")
+    })
+    .map(|_| ())
+}
+
+pub(crate) async fn set_bitrate_template(
+    executor: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    expression: Option<&Expr>,
+) -> Result<()> {
+    query!(
+        "UPDATE template_channels SET bitrate_template = $3 WHERE guild_id = $1 AND channel_id = \
+         $2;",
+        guild_id.get() as i64,
+        channel_id.get() as i64,
+        expression.map(ToString::to_string)
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Setting bitrate template in database for server with id {guild_id} failed!")
+    })
+    .map(|_| ())
+}
+
+pub(crate) async fn set_user_limit_template(
+    executor: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    expression: Option<&Expr>,
+) -> Result<()> {
+    query!(
+        "UPDATE template_channels SET user_limit_template = $3 WHERE guild_id = $1 AND channel_id \
+         = $2;",
+        guild_id.get() as i64,
+        channel_id.get() as i64,
+        expression.map(ToString::to_string)
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Setting user limit template in database for server with id {guild_id} failed!")
+    })
+    .map(|_| ())
+}
+
+pub(crate) async fn set_numbering(
+    executor: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    numbering: NumberingStrategy,
+) -> Result<()> {
+    query!(
+        "UPDATE template_channels SET numbering = $3 WHERE guild_id = $1 AND channel_id = $2;",
+        guild_id.get() as i64,
+        channel_id.get() as i64,
+        numbering.name()
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Setting numbering strategy in database for server with id {guild_id} failed!")
+    })
+    .map(|_| ())
+}
+
+pub(crate) async fn set_first_child_number(
+    executor: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    first_child_number: u64,
+) -> Result<()> {
+    query!(
+        "UPDATE template_channels SET first_child_number = $3 WHERE guild_id = $1 AND channel_id \
+         = $2;",
+        guild_id.get() as i64,
+        channel_id.get() as i64,
+        first_child_number as i64
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Setting first child number in database for server with id {guild_id} failed!")
+    })
+    .map(|_| ())
+}
+
+pub(crate) async fn set_max_children(
+    executor: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    max_children: Option<u64>,
+) -> Result<()> {
+    query!(
+        "UPDATE template_channels SET max_children = $3 WHERE guild_id = $1 AND channel_id = $2;",
+        guild_id.get() as i64,
+        channel_id.get() as i64,
+        max_children.map(|v| v as i64)
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Setting maximum children in database for server with id {guild_id} failed!")
+    })
+    .map(|_| ())
+}
+
+pub(crate) async fn set_grace_period(
+    executor: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    grace_period: Duration,
+) -> Result<()> {
+    query!(
+        "UPDATE template_channels SET grace_period = $3 WHERE guild_id = $1 AND channel_id = $2;",
+        guild_id.get() as i64,
+        channel_id.get() as i64,
+        grace_period.as_secs() as i64
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Setting grace period in database for server with id {guild_id} failed!")
+    })
+    .map(|_| ())
+}
+
+pub(crate) async fn set_ownership_transfer(
+    executor: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    transfer: OwnershipTransfer,
+) -> Result<()> {
+    query!(
+        "UPDATE template_channels SET ownership_transfer = $3 WHERE guild_id = $1 AND channel_id \
+         = $2;",
+        guild_id.get() as i64,
+        channel_id.get() as i64,
+        transfer.name()
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Setting ownership transfer in database for server with id {guild_id} failed!")
+    })
+    .map(|_| ())
+}
+
+/// Makes new children of a parent copy a setting of it, or stop copying it.
+pub(crate) async fn set_inherited(
+    executor: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    setting: ChildSetting,
+    inherit: bool,
+) -> Result<()> {
+    query!(
+        "UPDATE template_channels SET uninherited = CASE WHEN $4 THEN array_remove(uninherited, \
+         $3) ELSE array_append(array_remove(uninherited, $3), $3) END WHERE guild_id = $1 AND \
+         channel_id = $2;",
+        guild_id.get() as i64,
+        channel_id.get() as i64,
+        setting.name(),
+        inherit
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Setting inherited settings in database for server with id {guild_id} failed!")
+    })
+    .map(|_| ())
+}
+
+/// Makes a member the owner of a child.
+pub(crate) async fn set_child_owner(
+    executor: &PgPool,
+    child_id: ChannelId,
+    owner_id: UserId,
+) -> Result<()> {
+    query!(
+        "UPDATE child_channels SET owner_id = $2, owner_left_at = NULL WHERE child_id = $1;",
+        child_id.get() as i64,
+        owner_id.get() as i64
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| eyre!("Setting owner of child with id {child_id} failed!"))
+    .map(|_| ())
+}
+
+/// Records whether the owner of a child has left it, and since when.
+pub(crate) async fn set_owner_left(
+    executor: &PgPool,
+    child_id: ChannelId,
+    left: bool,
+) -> Result<()> {
+    query!(
+        "UPDATE child_channels SET owner_left_at = CASE WHEN $2 THEN now() END WHERE child_id = \
+         $1;",
+        child_id.get() as i64,
+        left
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| eyre!("Recording owner of child with id {child_id} leaving failed!"))
+    .map(|_| ())
+}
+
+/// Makes a member the owner of a child if its owner left at least `timeout`
+/// ago, or at an unknown time. Returns whether the child was claimed.
+pub(crate) async fn claim_child(
+    executor: &PgPool,
+    child_id: ChannelId,
+    owner_id: UserId,
+    timeout: Duration,
+) -> Result<bool> {
+    query!(
+        "UPDATE child_channels SET owner_id = $2, owner_left_at = NULL WHERE child_id = $1 AND \
+         (owner_left_at IS NULL OR owner_left_at <= now() - $3 * INTERVAL '1 second');",
+        child_id.get() as i64,
+        owner_id.get() as i64,
+        timeout.as_secs_f64()
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| eyre!("Claiming child with id {child_id} failed!"))
+    .map(|result| result.rows_affected() == 1)
+}
+
+/// Records that a child has become empty, unless it already was, and returns
+/// how much of the grace period of its parent is left. The time is stored so
+/// the deletion can be rescheduled after a restart.
+pub(crate) async fn mark_child_empty(executor: &PgPool, child_id: ChannelId) -> Result<Duration> {
+    query!(
+        r#"
+        UPDATE child_channels
+        SET empty_since = COALESCE(empty_since, now())
+        FROM template_channels
+        WHERE child_id = $1 AND channel_id = parent_id
+        RETURNING GREATEST(
+            0,
+            EXTRACT(EPOCH FROM empty_since + grace_period * INTERVAL '1 second' - now()) * 1000
+        )::BIGINT AS "remaining_millis!"
+        "#,
+        child_id.get() as i64
+    )
+    .fetch_one(executor)
+    .await
+    .wrap_err_with(|| eyre!("Marking child with id {child_id} as empty failed!"))
+    .map(|row| Duration::from_millis(row.remaining_millis as u64))
+}
+
+/// Records that a child which was waiting to be deleted is in use again.
+pub(crate) async fn mark_child_occupied(executor: &PgPool, child_id: ChannelId) -> Result<()> {
+    query!(
+        "UPDATE child_channels SET empty_since = NULL WHERE child_id = $1;",
+        child_id.get() as i64
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| eyre!("Marking child with id {child_id} as occupied failed!"))
+    .map(|_| ())
+}
+
+/// Records that children which may have been waiting to be deleted are in use.
+pub(crate) async fn mark_children_occupied(executor: &PgPool, child_ids: &[i64]) -> Result<()> {
+    query!(
+        "UPDATE child_channels SET empty_since = NULL WHERE child_id = ANY($1);",
+        child_ids
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| eyre!("Marking children with ids {child_ids:?} as occupied failed!"))
+    .map(|_| ())
+}
+
+/// Moves a parent into a numbering group, or out of its group if `group` is
+/// `None`.
+pub(crate) async fn set_numbering_group(
+    executor: &PgPool,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    group: Option<&str>,
+) -> Result<()> {
+    query!(
+        "UPDATE template_channels SET numbering_group = $3 WHERE guild_id = $1 AND channel_id = \
+         $2;",
+        guild_id.get() as i64,
+        channel_id.get() as i64,
+        group
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Setting numbering group in database for server with id {guild_id} failed!")
+    })
+    .map(|_| ())
+}
+
+pub(crate) async fn set_variable(
+    executor: &PgPool,
+    guild_id: GuildId,
+    name: &str,
+    value: &str,
+) -> Result<()> {
+    query!(
+        "INSERT INTO template_variables (guild_id, variable_name, variable_value) VALUES ($1, $2, \
+         $3) ON CONFLICT (guild_id, variable_name) DO UPDATE SET variable_value = $3;",
+        guild_id.get() as i64,
+        name,
+        value
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| eyre!("Setting variable in database for server with id {guild_id} failed!"))
+    .map(|_| ())
+}
+
+/// Deletes a variable, returning whether it existed.
+pub(crate) async fn delete_variable(
+    executor: &PgPool,
+    guild_id: GuildId,
+    name: &str,
+) -> Result<bool> {
+    query!(
+        "DELETE FROM template_variables WHERE guild_id = $1 AND variable_name = $2;",
+        guild_id.get() as i64,
+        name
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Deleting variable from database for server with id {guild_id} failed!")
+    })
+    .map(|res| res.rows_affected() > 0)
+}
+
+pub(crate) async fn get_variables(
+    executor: &PgPool,
+    guild_id: GuildId,
+) -> Result<HashMap<String, String>> {
+    query!(
+        "SELECT variable_name, variable_value FROM template_variables WHERE guild_id = $1;",
+        guild_id.get() as i64
+    )
+    .fetch_all(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Getting variables from database for server with id {guild_id} failed!")
+    })
+    .map(|rows| {
+        rows.into_iter()
+            .map(|row| (row.variable_name, row.variable_value))
+            .collect()
+    })
+}
+
+pub(crate) async fn set_name_pool(
+    executor: &PgPool,
+    guild_id: GuildId,
+    name: &str,
+    entries: &[String],
+) -> Result<()> {
+    query!(
+        "INSERT INTO name_pools (guild_id, pool_name, entries) VALUES ($1, $2, $3) ON CONFLICT \
+         (guild_id, pool_name) DO UPDATE SET entries = $3;",
+        guild_id.get() as i64,
+        name,
+        entries
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| eyre!("Setting name pool in database for server with id {guild_id} failed!"))
+    .map(|_| ())
+}
+
+/// Deletes a name pool, returning whether it existed.
+pub(crate) async fn delete_name_pool(
+    executor: &PgPool,
+    guild_id: GuildId,
+    name: &str,
+) -> Result<bool> {
+    query!(
+        "DELETE FROM name_pools WHERE guild_id = $1 AND pool_name = $2;",
+        guild_id.get() as i64,
+        name
+    )
+    .execute(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Deleting name pool from database for server with id {guild_id} failed!")
+    })
+    .map(|res| res.rows_affected() > 0)
+}
+
+pub(crate) async fn get_name_pools(
+    executor: &PgPool,
+    guild_id: GuildId,
+) -> Result<HashMap<String, Vec<String>>> {
+    query!(
+        "SELECT pool_name, entries FROM name_pools WHERE guild_id = $1;",
+        guild_id.get() as i64
+    )
+    .fetch_all(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!("Getting name pools from database for server with id {guild_id} failed!")
+    })
+    .map(|rows| {
+        rows.into_iter()
+            .map(|row| (row.pool_name, row.entries))
+            .collect()
+    })
+}
+
 #[derive(Debug, Clone, Default)]
 pub(crate) struct Child {
     pub(crate) id:                    ChannelId,
     pub(crate) number:                u64,
     pub(crate) total_children_number: u64,
-    pub(crate) template:              String,
+    /// The templates of the parent, shared with it.
+    pub(crate) templates:             Arc<ChannelTemplates>,
+    /// The member whose join created this child.
+    pub(crate) owner_id:              Option<UserId>,
+    /// The name pool the child was allocated an entry from.
+    pub(crate) pool_name:             Option<String>,
+    /// The entry of the name pool allocated to this child.
+    pub(crate) pool_entry:            Option<String>,
+    pub(crate) render_state:          RenderState,
+    /// The properties the owner has set by hand.
+    pub(crate) overrides:             ChannelOverrides,
 }
 
 impl Hash for Child {
@@ -234,19 +1066,108 @@ impl Eq for Child {}
 
 pub(crate) type Children = HashSet<Child>;
 
+/// How a parent numbers its children. The names double as the values stored
+/// in the database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, poise::ChoiceParameter)]
+pub(crate) enum NumberingStrategy {
+    /// New children get the lowest number no living sibling has.
+    #[default]
+    #[name = "lowest_free"]
+    LowestFree,
+    /// New children get a higher number than any sibling had before. Starts
+    /// over at 1 once every child is gone.
+    #[name = "monotonic"]
+    Monotonic,
+    /// Surviving children are renumbered after a deletion, so the numbers are
+    /// always 1..N.
+    #[name = "compact"]
+    Compact,
+}
+
+fn parse_numbering(numbering: &str) -> Result<NumberingStrategy> {
+    NumberingStrategy::from_name(numbering)
+        .ok_or_else(|| eyre!("Unknown numbering strategy `{numbering}`!"))
+}
+
+/// What happens to a child when its owner leaves while others stay. The names
+/// double as the values stored in the database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, poise::ChoiceParameter)]
+pub(crate) enum OwnershipTransfer {
+    /// Ownership passes to the member who has been in the child the longest.
+    #[default]
+    #[name = "automatic"]
+    Automatic,
+    /// The owner keeps the child, but the members in it can claim it once the
+    /// owner has been gone for a while.
+    #[name = "claim"]
+    Claim,
+}
+
+fn parse_ownership_transfer(transfer: &str) -> Result<OwnershipTransfer> {
+    OwnershipTransfer::from_name(transfer)
+        .ok_or_else(|| eyre!("Unknown ownership transfer `{transfer}`!"))
+}
+
+/// A setting of a parent that new children copy unless the parent opts out.
+/// The names double as the values stored in the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub(crate) enum ChildSetting {
+    #[name = "bitrate"]
+    Bitrate,
+    #[name = "rtc_region"]
+    RtcRegion,
+    #[name = "video_quality"]
+    VideoQuality,
+    #[name = "nsfw"]
+    Nsfw,
+    #[name = "permissions"]
+    Permissions,
+}
+
+fn parse_uninherited(settings: &[String]) -> Result<Vec<ChildSetting>> {
+    settings
+        .iter()
+        .map(|setting| {
+            ChildSetting::from_name(setting)
+                .ok_or_else(|| eyre!("Unknown child setting `{setting}`!"))
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone, Default)]
 pub(crate) struct Parent {
-    pub(crate) id:       ChannelId,
-    pub(crate) template: String,
-    pub(crate) capacity: Option<u64>,
+    pub(crate) id:                 ChannelId,
+    pub(crate) capacity:           Option<u64>,
+    pub(crate) numbering:          NumberingStrategy,
+    /// The group of parents whose children share one number space, if any.
+    pub(crate) numbering_group:    Option<String>,
+    /// The number the first child gets.
+    pub(crate) first_child_number: u64,
+    /// How many children the parent may have at once, if limited.
+    pub(crate) max_children:       Option<u64>,
+    /// How long an empty child is kept around before it is deleted.
+    pub(crate) grace_period:       Duration,
+    /// What happens to a child when its owner leaves.
+    pub(crate) ownership_transfer: OwnershipTransfer,
+    /// The settings new children don't copy from the parent.
+    pub(crate) uninherited:        Vec<ChildSetting>,
+    /// The parsed templates of the parent. A user limit template takes
+    /// precedence over the capacity.
+    pub(crate) templates:          Arc<ChannelTemplates>,
+}
+
+impl Parent {
+    /// Returns whether new children copy a setting of the parent.
+    pub(crate) fn inherits(&self, setting: ChildSetting) -> bool {
+        !self.uninherited.contains(&setting)
+    }
 }
 
 impl From<ChannelId> for Parent {
     fn from(parent_id: ChannelId) -> Self {
         Self {
-            id:       parent_id,
-            template: String::new(),
-            capacity: None,
+            id: parent_id,
+            ..Default::default()
         }
     }
 }
@@ -267,13 +1188,14 @@ impl Eq for Parent {}
 
 pub(crate) async fn get_all_children_of_parent(
     executor: &PgPool,
+    template_cache: &TemplateCache,
     guild_id: GuildId,
     channels: &[i64],
 ) -> Result<Option<(Parent, Children)>> {
     debug!("Guild id and channel id in get all children is: {guild_id}, {channels:?}");
     let res = query!(
         r#"
-        SELECT child_id as "child_id?", child_number as "child_number?", next_child_number, channel_template, channel_id, capacity
+        SELECT child_id as "child_id?", child_number as "child_number?", owner_id, pool_name, pool_entry, render_state as "render_state?: Json<RenderState>", overrides as "overrides?: Json<ChannelOverrides>", (SELECT COUNT(*) FROM child_channels AS siblings WHERE siblings.parent_id = channel_id) AS "children_count!", channel_id, capacity, numbering, numbering_group, first_child_number, max_children, grace_period, ownership_transfer, uninherited
         FROM template_channels
         LEFT JOIN child_channels
         ON parent_id = channel_id
@@ -306,14 +1228,23 @@ pub(crate) async fn get_all_children_of_parent(
 
     let parent_id = ChannelId::new(parent_row.channel_id as u64);
 
-    let template = parent_row.channel_template.clone();
+    let Some(templates) = templates_of(executor, template_cache, guild_id, parent_id).await? else {
+        return Ok(None);
+    };
 
     let capacity = parent_row.capacity.map(|v| v as u64);
 
     let parent = Parent {
         id: parent_id,
-        template,
         capacity,
+        numbering: parse_numbering(&parent_row.numbering)?,
+        numbering_group: parent_row.numbering_group.clone(),
+        first_child_number: parent_row.first_child_number as u64,
+        max_children: parent_row.max_children.map(|v| v as u64),
+        grace_period: Duration::from_secs(parent_row.grace_period as u64),
+        ownership_transfer: parse_ownership_transfer(&parent_row.ownership_transfer)?,
+        uninherited: parse_uninherited(&parent_row.uninherited)?,
+        templates,
     };
 
     let children = iter
@@ -322,13 +1253,21 @@ pub(crate) async fn get_all_children_of_parent(
 
             let child_number = row.child_number? as u64;
 
-            let total_children_number = row.next_child_number as u64 - 1;
-            let template = row.channel_template;
+            let total_children_number = row.children_count as u64;
+            let owner_id = row.owner_id.map(|v| UserId::new(v as u64));
             Some(Child {
                 id: child_id,
                 number: child_number,
                 total_children_number,
-                template,
+                templates: Arc::clone(&parent.templates),
+                owner_id,
+                pool_name: row.pool_name,
+                pool_entry: row.pool_entry,
+                render_state: row.render_state.map(|state| state.0).unwrap_or_default(),
+                overrides: row
+                    .overrides
+                    .map(|overrides| overrides.0)
+                    .unwrap_or_default(),
             })
         })
         .collect();
@@ -337,6 +1276,7 @@ pub(crate) async fn get_all_children_of_parent(
 }
 pub(crate) async fn get_all_channels_in_guild(
     executor: &PgPool,
+    template_cache: &TemplateCache,
     guild_id: GuildId,
 ) -> Result<HashMap<Parent, Children>> {
     info!("Retrieving all channels in guild with ID `{guild_id}`!");
@@ -347,7 +1287,7 @@ pub(crate) async fn get_all_channels_in_guild(
 
     let res = query!(
         r#"
-        SELECT channel_template, channel_id, child_id as "child_id?", child_number as "child_number?", next_child_number, capacity 
+        SELECT channel_id, child_id as "child_id?", child_number as "child_number?", owner_id, pool_name, pool_entry, render_state as "render_state?: Json<RenderState>", overrides as "overrides?: Json<ChannelOverrides>", (SELECT COUNT(*) FROM child_channels AS siblings WHERE siblings.parent_id = channel_id) AS "children_count!", capacity, numbering, numbering_group, first_child_number, max_children, grace_period, ownership_transfer, uninherited
         FROM template_channels
         LEFT JOIN child_channels ON template_channels.channel_id = child_channels.parent_id
         WHERE template_channels.guild_id = $1;
@@ -358,20 +1298,44 @@ pub(crate) async fn get_all_channels_in_guild(
     .await
     .wrap_err_with(|| eyre!("Getting all channels in guild with ID `{guild_id}` failed!"),)?;
 
-    let mut parent_channels = HashMap::default();
+    let mut parent_channels = HashMap::<Parent, Children>::default();
+    let mut broken_parents = HashSet::<ChannelId>::default();
 
     for row in res {
         let parent_id = ChannelId::new(row.channel_id as u64);
+        if broken_parents.contains(&parent_id) {
+            continue;
+        }
         let child_id = row.child_id.map(|v| ChannelId::new(v as u64));
         let child_number = row.child_number.map(|v| v as u64);
-        let next_child_number = row.next_child_number as u64;
-        let template = row.channel_template;
+        let owner_id = row.owner_id.map(|v| UserId::new(v as u64));
+        let children_count = row.children_count as u64;
         let capacity = row.capacity.map(|v| v as u64);
 
+        // Every row of a parent carries it, but its templates only need to be
+        // looked up once.
+        let templates = match parent_channels.get_key_value(&Parent::from(parent_id)) {
+            | Some((parent, _)) => Arc::clone(&parent.templates),
+            | None => match templates_of(executor, template_cache, guild_id, parent_id).await? {
+                | Some(templates) => templates,
+                | None => {
+                    broken_parents.insert(parent_id).drop();
+                    continue;
+                },
+            },
+        };
+
         let parent = Parent {
             id: parent_id,
-            template: template.clone(),
             capacity,
+            numbering: parse_numbering(&row.numbering)?,
+            numbering_group: row.numbering_group.clone(),
+            first_child_number: row.first_child_number as u64,
+            max_children: row.max_children.map(|v| v as u64),
+            grace_period: Duration::from_secs(row.grace_period as u64),
+            ownership_transfer: parse_ownership_transfer(&row.ownership_transfer)?,
+            uninherited: parse_uninherited(&row.uninherited)?,
+            templates: Arc::clone(&templates),
         };
 
         let children = parent_channels
@@ -385,8 +1349,13 @@ pub(crate) async fn get_all_channels_in_guild(
                 let child = Child {
                     id: child_id,
                     number: child_number,
-                    total_children_number: next_child_number,
-                    template,
+                    total_children_number: children_count,
+                    templates,
+                    owner_id,
+                    pool_name: row.pool_name,
+                    pool_entry: row.pool_entry,
+                    render_state: row.render_state.map(|state| state.0).unwrap_or_default(),
+                    overrides: row.overrides.map(|overrides| overrides.0).unwrap_or_default(),
                 };
                 children.insert(child).drop();
             }
@@ -396,79 +1365,137 @@ pub(crate) async fn get_all_channels_in_guild(
     Ok(parent_channels)
 }
 
-pub(crate) async fn update_next_child_number(
+/// Brings the numbers of a parent's children, and those of the rest of its
+/// numbering group, in line with its numbering strategy after one of them was
+/// deleted.
+pub(crate) async fn release_child_number(
     executor: &PgPool,
-    parent_id: ChannelId,
-    child_id: ChannelId,
+    guild_id: GuildId,
+    parent: &Parent,
 ) -> Result<()> {
-    let res = query!(
-        "
-        WITH
-            cn AS (
-                SELECT child_number FROM child_channels WHERE child_id=$2
-            ),
-            next AS (
-                SELECT COALESCE(MIN(child_number) + 1, 1) AS res
-                FROM child_channels
-                WHERE (parent_id = $1) AND
-                    (child_number + 1 NOT IN
-                        (
-                        SELECT child_number FROM child_channels
-                        )
-                    )
+    match parent.numbering {
+        | NumberingStrategy::LowestFree => Ok(()),
+        | NumberingStrategy::Monotonic => {
+            let mut transaction = executor
+                .begin()
+                .await
+                .wrap_err_with(|| eyre!("Failed to start a transaction!"))?;
+            lock_numbering_scope(
+                &mut transaction,
+                guild_id,
+                parent.id,
+                parent.numbering_group.as_deref(),
             )
-        UPDATE template_channels
-        SET next_child_number = 
-        CASE
-            WHEN (SELECT * FROM next) = (SELECT * FROM cn) + 1 THEN (SELECT * FROM cn)
-            ELSE (SELECT * FROM next)
-        END
-        WHERE channel_id = $1
-    ",
-        parent_id.get() as i64,
-        child_id.get() as i64
-    )
-    .execute(executor)
-    .await
-    .wrap_err_with(|| eyre!("Failed to update next_child_number for parent with id {parent_id}"))?;
-
-    let rows_affected = res.rows_affected();
-
-    assert_eq!(
-        rows_affected, 1,
-        "Sanity check failed: update_next_child_number updated {rows_affected} rows!"
-    );
-
-    Ok(())
+            .await?
+            .drop();
+            query!(
+                "
+                WITH scope AS (
+                    SELECT channel_id FROM template_channels
+                    WHERE channel_id = $1 OR (guild_id = $2 AND numbering_group = $3)
+                )
+                UPDATE template_channels SET next_child_number = 1
+                WHERE channel_id IN (SELECT channel_id FROM scope)
+                AND NOT EXISTS (
+                    SELECT 1 FROM child_channels WHERE parent_id IN (SELECT channel_id FROM scope)
+                )
+                ",
+                parent.id.get() as i64,
+                guild_id.get() as i64,
+                parent.numbering_group
+            )
+            .execute(&mut *transaction)
+            .await
+            .wrap_err_with(|| {
+                eyre!(
+                    "Failed to reset next_child_number for parent with id {}",
+                    parent.id
+                )
+            })?
+            .drop();
+            transaction
+                .commit()
+                .await
+                .wrap_err_with(|| eyre!("Failed to commit transaction!"))
+        },
+        | NumberingStrategy::Compact => compact_child_numbers(executor, guild_id, parent).await,
+    }
 }
 
-pub(crate) async fn init_next_child_number(executor: &PgPool) -> Result<()> {
-    #[rustfmt::skip]
+/// Renumbers the children of a parent, or of its whole numbering group if it
+/// has one, to consecutive numbers starting at the first number of the parent,
+/// keeping their order.
+pub(crate) async fn compact_child_numbers(
+    executor: &PgPool,
+    guild_id: GuildId,
+    parent: &Parent,
+) -> Result<()> {
+    let parent_id = parent.id;
+    let numbering_group = parent.numbering_group.as_deref();
+    // The scope stays locked while renumbering, so that a child created
+    // concurrently can't be allocated a number that is being handed out here.
+    let mut transaction = executor
+        .begin()
+        .await
+        .wrap_err_with(|| eyre!("Failed to start a transaction!"))?;
+    lock_numbering_scope(&mut transaction, guild_id, parent_id, numbering_group)
+        .await?
+        .drop();
+    let children =
+        numbered_children(&mut transaction, guild_id, parent_id, numbering_group).await?;
+    let (child_ids, new_numbers): (Vec<i64>, Vec<i64>) =
+        compact_numbers(parent.first_child_number as i64, children)
+            .into_iter()
+            .unzip();
     let rows_affected = query!(
-        "
-        WITH next AS (
-            SELECT MIN(child_number) + 1 AS res, parent_id
-            FROM child_channels
-            GROUP BY parent_id
-        )
-        UPDATE template_channels
-        SET next_child_number = 
-        CASE
-            WHEN EXISTS (SELECT * FROM next) THEN (SELECT COALESCE(res, 1) FROM next RIGHT JOIN \
-                template_channels ON parent_id = channel_id)
-            ELSE 1
-        END
-    "
+        "UPDATE child_channels SET child_number = renumbered.child_number FROM \
+         UNNEST($1::BIGINT[], $2::BIGINT[]) AS renumbered(child_id, child_number) WHERE \
+         child_channels.child_id = renumbered.child_id;",
+        &child_ids,
+        &new_numbers
     )
-    .execute(executor)
+    .execute(&mut *transaction)
     .await
-    .wrap_err_with(|| eyre!("Failed to initialize next_child_number!"))?
+    .wrap_err_with(|| eyre!("Failed to compact child numbers of parent with id {parent_id}"))?
     .rows_affected();
+    transaction
+        .commit()
+        .await
+        .wrap_err_with(|| eyre!("Failed to commit transaction!"))?;
 
-    info!("Updated {rows_affected} rows in init_next_child_number!");
+    debug!("Renumbered {rows_affected} children sharing numbers with parent {parent_id}!");
     Ok(())
 }
 
+/// Returns the parents that share numbers with the given parent, including the
+/// parent itself.
+pub(crate) async fn get_numbering_scope(
+    executor: &PgPool,
+    guild_id: GuildId,
+    parent: &Parent,
+) -> Result<Vec<ChannelId>> {
+    query!(
+        "SELECT channel_id FROM template_channels WHERE channel_id = $1 OR (guild_id = $2 AND \
+         numbering_group = $3);",
+        parent.id.get() as i64,
+        guild_id.get() as i64,
+        parent.numbering_group
+    )
+    .fetch_all(executor)
+    .await
+    .wrap_err_with(|| {
+        eyre!(
+            "Getting numbering group of parent with id {} failed!",
+            parent.id
+        )
+    })
+    .map(|rows| {
+        rows.into_iter()
+            .map(|row| ChannelId::new(row.channel_id as u64))
+            .collect()
+    })
+}
+
 pub(crate) async fn remove_dead_channels(
     executor: &PgPool,
     deleted_parents: &[i64],
@@ -502,3 +1529,126 @@ pub(crate) async fn remove_dead_channels(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(NumberingStrategy::LowestFree, 1, 1, &[], 1)]
+    #[case(NumberingStrategy::LowestFree, 1, 4, &[1, 3], 2)]
+    #[case(NumberingStrategy::LowestFree, 1, 4, &[1, 2, 3], 4)]
+    #[case(NumberingStrategy::LowestFree, 0, 1, &[1], 0)]
+    #[case(NumberingStrategy::Monotonic, 1, 1, &[], 1)]
+    #[case(NumberingStrategy::Monotonic, 1, 4, &[1], 4)]
+    #[case(NumberingStrategy::Monotonic, 1, 2, &[5], 6)]
+    #[case(NumberingStrategy::Monotonic, 10, 1, &[], 10)]
+    #[case(NumberingStrategy::Compact, 1, 1, &[1, 2], 3)]
+    #[case(NumberingStrategy::Compact, 1, 3, &[2, 3], 1)]
+    fn test_pick_child_number(
+        #[case] numbering: NumberingStrategy,
+        #[case] first_number: i64,
+        #[case] next_number: i64,
+        #[case] taken_numbers: &[i64],
+        #[case] expected: i64,
+    ) {
+        assert_eq!(
+            expected,
+            pick_child_number(numbering, first_number, next_number, taken_numbers)
+        );
+    }
+
+    #[rstest]
+    #[case(0, None, false)]
+    #[case(100, None, false)]
+    #[case(1, Some(2), false)]
+    #[case(2, Some(2), true)]
+    #[case(3, Some(2), true)]
+    #[case(0, Some(0), true)]
+    fn test_is_at_max_children(
+        #[case] children_count: u64,
+        #[case] max_children: Option<u64>,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(expected, is_at_max_children(children_count, max_children));
+    }
+
+    #[rstest]
+    #[case(1, vec![], vec![])]
+    #[case(1, vec![(10, 1), (11, 2)], vec![])]
+    #[case(1, vec![(10, 1), (12, 3)], vec![(12, 2)])]
+    #[case(1, vec![(12, 5), (10, 2), (11, 2)], vec![(10, 1), (12, 3)])]
+    #[case(0, vec![(10, 1), (11, 2)], vec![(10, 0), (11, 1)])]
+    fn test_compact_numbers(
+        #[case] first_number: i64,
+        #[case] children: Vec<(i64, i64)>,
+        #[case] expected: Vec<(i64, i64)>,
+    ) {
+        assert_eq!(expected, compact_numbers(first_number, children));
+    }
+
+    fn templates(name: &str) -> Arc<ChannelTemplates> {
+        Arc::new(ChannelTemplates::parse(name, None, None, None).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_template_cache_evicts_parents() {
+        let cache = TemplateCache::default();
+        let (guild_id, lobby, room) = (GuildId::new(1), ChannelId::new(2), ChannelId::new(3));
+        let generation = cache.generation().await;
+        cache
+            .insert(generation, guild_id, lobby, templates("Lobby {#}"))
+            .await;
+        cache
+            .insert(generation, guild_id, room, templates("Room {#}"))
+            .await;
+        assert_eq!(
+            "Lobby {#}",
+            cache.get(guild_id, lobby).await.unwrap().name.to_string()
+        );
+
+        cache.evict_parents(guild_id, [lobby]).await;
+        assert!(cache.get(guild_id, lobby).await.is_none());
+        assert!(cache.get(guild_id, room).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_template_cache_ignores_templates_loaded_before_eviction() {
+        let cache = TemplateCache::default();
+        let (guild_id, lobby) = (GuildId::new(1), ChannelId::new(2));
+        let generation = cache.generation().await;
+        cache.evict_parents(guild_id, [lobby]).await;
+        cache
+            .insert(generation, guild_id, lobby, templates("Lobby {#}"))
+            .await;
+        assert!(cache.get(guild_id, lobby).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_template_cache_evicts_guilds() {
+        let cache = TemplateCache::default();
+        let lobby = ChannelId::new(10);
+        let generation = cache.generation().await;
+        for guild_id in 1..=3 {
+            cache
+                .insert(
+                    generation,
+                    GuildId::new(guild_id),
+                    lobby,
+                    templates("Lobby"),
+                )
+                .await;
+        }
+
+        cache.evict_guild(GuildId::new(1)).await;
+        cache
+            .retain_guilds(&[GuildId::new(1), GuildId::new(2)])
+            .await;
+        assert!(cache.get(GuildId::new(1), lobby).await.is_none());
+        assert!(cache.get(GuildId::new(2), lobby).await.is_some());
+        assert!(cache.get(GuildId::new(3), lobby).await.is_none());
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    error::Error,
+    fmt::{
+        self,
+        Display,
+        Formatter,
+    },
+};
+
+/// A value an expression can refer to.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub(crate) enum Operand {
+    /// `#`, the number of the child.
+    ChannelNumber,
+    /// `%`, the number of living siblings including the child itself.
+    ChildrenInTotal,
+    /// `users`, the number of members connected to the child.
+    Users,
+    /// `cap`, the capacity of the child. Unbounded if it has none.
+    Capacity,
+    Literal(i64),
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub(crate) enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// An arithmetic expression inside template braces, such as `# + 100`.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub(crate) enum Expr {
+    Operand(Operand),
+    Negate(Box<Expr>),
+    Binary {
+        op:  BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+}
+
+/// Why an expression couldn't be evaluated.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub(crate) enum EvalError {
+    Overflow,
+    DivisionByZero,
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            | Self::Overflow => write!(f, "arithmetic overflow in template expression"),
+            | Self::DivisionByZero => write!(f, "division by zero in template expression"),
+        }
+    }
+}
+
+impl Error for EvalError {}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            | Self::ChannelNumber => write!(f, "#"),
+            | Self::ChildrenInTotal => write!(f, "%"),
+            | Self::Users => write!(f, "users"),
+            | Self::Capacity => write!(f, "cap"),
+            | Self::Literal(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            | Self::Add => write!(f, "+"),
+            | Self::Sub => write!(f, "-"),
+            | Self::Mul => write!(f, "*"),
+            | Self::Div => write!(f, "/"),
+        }
+    }
+}
+
+/// Writes the expression with spaces around binary operators and only the
+/// parentheses its structure requires, e.g. `(# + 1) * 2`.
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_with_precedence(f, 0)
+    }
+}
+
+impl Expr {
+    pub(crate) fn binary(op: BinaryOp, lhs: Self, rhs: Self) -> Self {
+        Self::Binary {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    /// How tightly the expression binds. Higher binds tighter.
+    fn precedence(&self) -> u8 {
+        match self {
+            | Self::Binary {
+                op: BinaryOp::Add | BinaryOp::Sub,
+                ..
+            } => 1,
+            | Self::Binary { .. } => 2,
+            | Self::Negate(_) => 3,
+            | Self::Operand(_) => 4,
+        }
+    }
+
+    /// Writes the expression, parenthesized if it binds looser than `min`.
+    fn fmt_with_precedence(&self, f: &mut Formatter<'_>, min: u8) -> fmt::Result {
+        let precedence = self.precedence();
+        if precedence < min {
+            return write!(f, "({self})");
+        }
+        match self {
+            | Self::Operand(operand) => write!(f, "{operand}"),
+            | Self::Negate(expr) => {
+                write!(f, "-")?;
+                expr.fmt_with_precedence(f, precedence)
+            },
+            // Operators are left associative, so only the right hand side needs
+            // parentheses at equal precedence.
+            | Self::Binary { op, lhs, rhs } => {
+                lhs.fmt_with_precedence(f, precedence)?;
+                write!(f, " {op} ")?;
+                rhs.fmt_with_precedence(f, precedence + 1)
+            },
+        }
+    }
+
+    /// Evaluates the expression with checked arithmetic. `value_of` returns
+    /// `None` for unbounded operands, which makes the whole expression
+    /// unbounded.
+    pub(crate) fn evaluate(
+        &self,
+        value_of: &impl Fn(Operand) -> Option<i64>,
+    ) -> Result<Option<i64>, EvalError> {
+        match self {
+            | Self::Operand(Operand::Literal(value)) => Ok(Some(*value)),
+            | Self::Operand(operand) => Ok(value_of(*operand)),
+            | Self::Negate(expr) => expr
+                .evaluate(value_of)?
+                .map(|value| value.checked_neg().ok_or(EvalError::Overflow))
+                .transpose(),
+            | Self::Binary { op, lhs, rhs } => {
+                let (Some(lhs), Some(rhs)) = (lhs.evaluate(value_of)?, rhs.evaluate(value_of)?)
+                else {
+                    return Ok(None);
+                };
+                let value = match op {
+                    | BinaryOp::Add => lhs.checked_add(rhs),
+                    | BinaryOp::Sub => lhs.checked_sub(rhs),
+                    | BinaryOp::Mul => lhs.checked_mul(rhs),
+                    | BinaryOp::Div if rhs == 0 => return Err(EvalError::DivisionByZero),
+                    | BinaryOp::Div => lhs.checked_div(rhs),
+                };
+                value.ok_or(EvalError::Overflow).map(Some)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn literal(value: i64) -> Expr {
+        Expr::Operand(Operand::Literal(value))
+    }
+
+    fn value_of(operand: Operand) -> Option<i64> {
+        match operand {
+            | Operand::ChannelNumber => Some(3),
+            | Operand::ChildrenInTotal => Some(5),
+            | Operand::Users => Some(2),
+            | Operand::Capacity | Operand::Literal(_) => None,
+        }
+    }
+
+    #[rstest]
+    #[case(
+        Expr::binary(BinaryOp::Add, Expr::Operand(Operand::ChannelNumber), literal(100)),
+        Ok(Some(103))
+    )]
+    #[case(
+        Expr::binary(
+            BinaryOp::Sub,
+            Expr::Operand(Operand::ChildrenInTotal),
+            Expr::Operand(Operand::ChannelNumber)
+        ),
+        Ok(Some(2))
+    )]
+    #[case(Expr::binary(BinaryOp::Div, literal(7), literal(2)), Ok(Some(3)))]
+    #[case(Expr::Negate(Box::new(Expr::Operand(Operand::Users))), Ok(Some(-2)))]
+    #[case(
+        Expr::binary(
+            BinaryOp::Sub,
+            Expr::Operand(Operand::Capacity),
+            Expr::Operand(Operand::Users)
+        ),
+        Ok(None)
+    )]
+    #[case(
+        Expr::binary(BinaryOp::Mul, literal(i64::MAX), literal(2)),
+        Err(EvalError::Overflow)
+    )]
+    #[case(
+        Expr::Negate(Box::new(Expr::binary(BinaryOp::Sub, literal(-1), literal(i64::MAX)))),
+        Err(EvalError::Overflow)
+    )]
+    #[case(
+        Expr::binary(
+            BinaryOp::Div,
+            Expr::Operand(Operand::Users),
+            Expr::binary(BinaryOp::Sub, Expr::Operand(Operand::Users), literal(2))
+        ),
+        Err(EvalError::DivisionByZero)
+    )]
+    fn test_evaluate(#[case] expr: Expr, #[case] expected: Result<Option<i64>, EvalError>) {
+        assert_eq!(expected, expr.evaluate(&value_of));
+    }
+
+    #[rstest]
+    #[case(
+        Expr::binary(BinaryOp::Add, Expr::Operand(Operand::ChannelNumber), literal(100)),
+        "# + 100"
+    )]
+    #[case(
+        Expr::binary(
+            BinaryOp::Mul,
+            Expr::binary(BinaryOp::Add, literal(1), literal(2)),
+            literal(3)
+        ),
+        "(1 + 2) * 3"
+    )]
+    #[case(
+        Expr::binary(
+            BinaryOp::Sub,
+            literal(1),
+            Expr::binary(BinaryOp::Sub, literal(2), literal(3))
+        ),
+        "1 - (2 - 3)"
+    )]
+    #[case(
+        Expr::binary(
+            BinaryOp::Sub,
+            Expr::binary(BinaryOp::Sub, literal(1), literal(2)),
+            literal(3)
+        ),
+        "1 - 2 - 3"
+    )]
+    #[case(
+        Expr::Negate(Box::new(Expr::binary(
+            BinaryOp::Div,
+            Expr::Operand(Operand::Capacity),
+            literal(2)
+        ))),
+        "-(cap / 2)"
+    )]
+    fn test_display(#[case] expr: Expr, #[case] expected: &str) {
+        assert_eq!(expected, expr.to_string());
+    }
+}
@@ -0,0 +1,499 @@
+use std::{
+    fmt::{
+        self,
+        Display,
+        Formatter,
+    },
+    str::FromStr,
+};
+
+use eyre::{
+    eyre,
+    Report,
+};
+
+use super::updater::MAX_CHANNEL_NAME_LENGTH;
+use crate::locale::Locale;
+
+/// How a numeric directive such as `{#}` is rendered.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub(crate) enum NumberFormat {
+    /// Plain decimal digits, e.g. `7`.
+    #[default]
+    Plain,
+    /// Decimal digits padded with zeros to the given width, e.g. `07`.
+    ZeroPadded(usize),
+    /// Upper case Roman numerals, e.g. `VII`.
+    Roman,
+    /// The number spelled out in words in the guild's locale, e.g. `seven`.
+    Words,
+    /// The number as an ordinal in the guild's locale, e.g. `7th`.
+    Ordinal,
+    /// Keycap emoji digits, e.g. `7️⃣`.
+    Emoji,
+    /// Letters counting A, B, ..., Z, AA, AB, ...
+    Letters,
+}
+
+impl FromStr for NumberFormat {
+    type Err = Report;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        Ok(match spec {
+            | "roman" => Self::Roman,
+            | "words" => Self::Words,
+            | "ordinal" => Self::Ordinal,
+            | "emoji" => Self::Emoji,
+            | "letter" | "letters" => Self::Letters,
+            | width if !width.is_empty() && width.bytes().all(|b| b.is_ascii_digit()) =>
+                match width.parse() {
+                    | Ok(width) if width <= MAX_CHANNEL_NAME_LENGTH => Self::ZeroPadded(width),
+                    | _ => return Err(eyre!("padding width `{width}` is too large")),
+                },
+            | _ => return Err(eyre!("unknown format specifier `{spec}`")),
+        })
+    }
+}
+
+/// Writes the specifier that parses back to the format, which is empty for
+/// [`NumberFormat::Plain`].
+impl Display for NumberFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            | Self::Plain => Ok(()),
+            | Self::ZeroPadded(width) => write!(f, "0{width}"),
+            | Self::Roman => write!(f, "roman"),
+            | Self::Words => write!(f, "words"),
+            | Self::Ordinal => write!(f, "ordinal"),
+            | Self::Emoji => write!(f, "emoji"),
+            | Self::Letters => write!(f, "letters"),
+        }
+    }
+}
+
+impl NumberFormat {
+    pub(crate) fn format(self, number: u64, locale: Locale) -> String {
+        match self {
+            | Self::Plain => number.to_string(),
+            | Self::ZeroPadded(width) => format!("{number:0width$}"),
+            | Self::Roman => to_roman(number),
+            | Self::Words => match locale {
+                | Locale::English => to_english_words(number),
+                | Locale::Swedish => to_swedish_words(number),
+                | Locale::German => to_german_words(number),
+            },
+            | Self::Ordinal => to_ordinal(number, locale),
+            | Self::Emoji => to_emoji(number),
+            | Self::Letters => to_letters(number),
+        }
+    }
+
+    /// Returns true if the format renders differently depending on the locale.
+    pub(crate) fn is_locale_dependent(self) -> bool {
+        matches!(self, Self::Words | Self::Ordinal)
+    }
+}
+
+/// Numbers above this can't be written with standard Roman numerals and are
+/// rendered as plain digits instead.
+const MAX_ROMAN: u64 = 3999;
+
+fn to_roman(mut number: u64) -> String {
+    const NUMERALS: [(u64, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    if number == 0 {
+        return "N".to_owned();
+    }
+    if number > MAX_ROMAN {
+        return number.to_string();
+    }
+    let mut res = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            res.push_str(numeral);
+            number -= value;
+        }
+    }
+    res
+}
+
+fn to_english_words(number: u64) -> String {
+    const ONES: [&str; 20] = [
+        "zero",
+        "one",
+        "two",
+        "three",
+        "four",
+        "five",
+        "six",
+        "seven",
+        "eight",
+        "nine",
+        "ten",
+        "eleven",
+        "twelve",
+        "thirteen",
+        "fourteen",
+        "fifteen",
+        "sixteen",
+        "seventeen",
+        "eighteen",
+        "nineteen",
+    ];
+    const TENS: [&str; 10] = [
+        "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+    ];
+    const SCALES: [(u64, &str); 6] = [
+        (1_000_000_000_000_000_000, "quintillion"),
+        (1_000_000_000_000_000, "quadrillion"),
+        (1_000_000_000_000, "trillion"),
+        (1_000_000_000, "billion"),
+        (1_000_000, "million"),
+        (1_000, "thousand"),
+    ];
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "Only numbers below a thousand are indexed with."
+    )]
+    fn below_thousand(number: u64) -> String {
+        let (hundreds, rest) = (number / 100, number % 100);
+        let rest = match rest {
+            | 0 => String::new(),
+            | 1..=19 => ONES[rest as usize].to_owned(),
+            | _ if rest % 10 == 0 => TENS[(rest / 10) as usize].to_owned(),
+            | _ => format!(
+                "{}-{}",
+                TENS[(rest / 10) as usize],
+                ONES[(rest % 10) as usize]
+            ),
+        };
+        match (hundreds, rest.is_empty()) {
+            | (0, _) => rest,
+            | (_, true) => format!("{} hundred", ONES[hundreds as usize]),
+            | (_, false) => format!("{} hundred {rest}", ONES[hundreds as usize]),
+        }
+    }
+
+    if number == 0 {
+        return ONES[0].to_owned();
+    }
+    let mut words = Vec::new();
+    let mut rest = number;
+    for (scale, name) in SCALES {
+        if rest >= scale {
+            words.push(format!("{} {name}", below_thousand(rest / scale)));
+            rest %= scale;
+        }
+    }
+    if rest > 0 {
+        words.push(below_thousand(rest));
+    }
+    words.join(" ")
+}
+
+/// Spells out a number in Swedish, which writes everything below a million as
+/// one word and uses the long scale above that.
+fn to_swedish_words(number: u64) -> String {
+    const ONES: [&str; 20] = [
+        "noll", "ett", "två", "tre", "fyra", "fem", "sex", "sju", "åtta", "nio", "tio", "elva",
+        "tolv", "tretton", "fjorton", "femton", "sexton", "sjutton", "arton", "nitton",
+    ];
+    const TENS: [&str; 10] = [
+        "", "", "tjugo", "trettio", "fyrtio", "femtio", "sextio", "sjuttio", "åttio", "nittio",
+    ];
+    const SCALES: [(u64, &str, &str); 5] = [
+        (1_000_000_000_000_000_000, "triljon", "triljoner"),
+        (1_000_000_000_000_000, "biljard", "biljarder"),
+        (1_000_000_000_000, "biljon", "biljoner"),
+        (1_000_000_000, "miljard", "miljarder"),
+        (1_000_000, "miljon", "miljoner"),
+    ];
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "Only numbers below a thousand are indexed with."
+    )]
+    fn below_thousand(number: u64) -> String {
+        let (hundreds, rest) = (number / 100, number % 100);
+        let mut words = String::new();
+        if hundreds > 0 {
+            words.push_str(ONES[hundreds as usize]);
+            words.push_str("hundra");
+        }
+        match rest {
+            | 0 => {},
+            | 1..=19 => words.push_str(ONES[rest as usize]),
+            | _ => {
+                words.push_str(TENS[(rest / 10) as usize]);
+                if rest % 10 > 0 {
+                    words.push_str(ONES[(rest % 10) as usize]);
+                }
+            },
+        }
+        words
+    }
+
+    if number == 0 {
+        return ONES[0].to_owned();
+    }
+    let mut words = Vec::new();
+    let mut rest = number;
+    for (scale, singular, plural) in SCALES {
+        match rest / scale {
+            | 0 => {},
+            | 1 => words.push(format!("en {singular}")),
+            | count => words.push(format!("{} {plural}", below_thousand(count))),
+        }
+        rest %= scale;
+    }
+    if rest > 0 {
+        let mut word = String::new();
+        if rest >= 1000 {
+            // "ett" and "tusen" share a t, as in "ettusen".
+            word.push_str(&format!("{}tusen", below_thousand(rest / 1000)).replace("ttt", "tt"));
+        }
+        word.push_str(&below_thousand(rest % 1000));
+        words.push(word);
+    }
+    words.join(" ")
+}
+
+/// Spells out a number in German, which writes everything below a million as
+/// one word and uses the long scale above that.
+fn to_german_words(number: u64) -> String {
+    const ONES: [&str; 20] = [
+        "null",
+        "eins",
+        "zwei",
+        "drei",
+        "vier",
+        "fünf",
+        "sechs",
+        "sieben",
+        "acht",
+        "neun",
+        "zehn",
+        "elf",
+        "zwölf",
+        "dreizehn",
+        "vierzehn",
+        "fünfzehn",
+        "sechzehn",
+        "siebzehn",
+        "achtzehn",
+        "neunzehn",
+    ];
+    const TENS: [&str; 10] = [
+        "", "", "zwanzig", "dreißig", "vierzig", "fünfzig", "sechzig", "siebzig", "achtzig",
+        "neunzig",
+    ];
+    const SCALES: [(u64, &str, &str); 5] = [
+        (1_000_000_000_000_000_000, "Trillion", "Trillionen"),
+        (1_000_000_000_000_000, "Billiarde", "Billiarden"),
+        (1_000_000_000_000, "Billion", "Billionen"),
+        (1_000_000_000, "Milliarde", "Milliarden"),
+        (1_000_000, "Million", "Millionen"),
+    ];
+
+    /// One is "eins" at the end of a number and "ein" everywhere else.
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "Only numbers below a thousand are indexed with."
+    )]
+    fn below_thousand(number: u64, ends_number: bool) -> String {
+        let digit = |digit: u64| {
+            if digit == 1 {
+                "ein"
+            } else {
+                ONES[digit as usize]
+            }
+        };
+        let (hundreds, rest) = (number / 100, number % 100);
+        let mut words = String::new();
+        if hundreds > 0 {
+            words.push_str(digit(hundreds));
+            words.push_str("hundert");
+        }
+        match rest {
+            | 0 => {},
+            | 1 if !ends_number => words.push_str("ein"),
+            | 1..=19 => words.push_str(ONES[rest as usize]),
+            | _ if rest % 10 == 0 => words.push_str(TENS[(rest / 10) as usize]),
+            | _ => {
+                words.push_str(digit(rest % 10));
+                words.push_str("und");
+                words.push_str(TENS[(rest / 10) as usize]);
+            },
+        }
+        words
+    }
+
+    if number == 0 {
+        return ONES[0].to_owned();
+    }
+    let mut words = Vec::new();
+    let mut rest = number;
+    for (scale, singular, plural) in SCALES {
+        match rest / scale {
+            | 0 => {},
+            | 1 => words.push(format!("eine {singular}")),
+            // The scales are feminine, so a count ending in one ends in "eine".
+            | count if count % 100 == 1 => {
+                words.push(format!("{}e {plural}", below_thousand(count, false)));
+            },
+            | count => words.push(format!("{} {plural}", below_thousand(count, false))),
+        }
+        rest %= scale;
+    }
+    if rest > 0 {
+        let mut word = String::new();
+        if rest >= 1000 {
+            word.push_str(&below_thousand(rest / 1000, false));
+            word.push_str("tausend");
+        }
+        word.push_str(&below_thousand(rest % 1000, true));
+        words.push(word);
+    }
+    words.join(" ")
+}
+
+fn to_ordinal(number: u64, locale: Locale) -> String {
+    match locale {
+        | Locale::English => {
+            let suffix = match (number % 10, number % 100) {
+                | (_, 11..=13) => "th",
+                | (1, _) => "st",
+                | (2, _) => "nd",
+                | (3, _) => "rd",
+                | _ => "th",
+            };
+            format!("{number}{suffix}")
+        },
+        | Locale::Swedish => {
+            let suffix = match (number % 10, number % 100) {
+                | (_, 11 | 12) => "e",
+                | (1 | 2, _) => "a",
+                | _ => "e",
+            };
+            format!("{number}:{suffix}")
+        },
+        | Locale::German => format!("{number}."),
+    }
+}
+
+fn to_emoji(number: u64) -> String {
+    number
+        .to_string()
+        .chars()
+        .flat_map(|digit| [digit, '\u{FE0F}', '\u{20E3}'])
+        .collect()
+}
+
+fn to_letters(number: u64) -> String {
+    if number == 0 {
+        return number.to_string();
+    }
+    let mut letters = Vec::new();
+    let mut rest = number;
+    while rest > 0 {
+        rest -= 1;
+        letters.push(char::from(b'A' + (rest % 26) as u8));
+        rest /= 26;
+    }
+    letters.into_iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(NumberFormat::Plain, 7, "7")]
+    #[case(NumberFormat::ZeroPadded(2), 7, "07")]
+    #[case(NumberFormat::ZeroPadded(2), 123, "123")]
+    #[case(NumberFormat::Roman, 4, "IV")]
+    #[case(NumberFormat::Roman, 1994, "MCMXCIV")]
+    #[case(NumberFormat::Roman, 4000, "4000")]
+    #[case(NumberFormat::Words, 0, "zero")]
+    #[case(NumberFormat::Words, 3, "three")]
+    #[case(NumberFormat::Words, 40, "forty")]
+    #[case(NumberFormat::Words, 42, "forty-two")]
+    #[case(NumberFormat::Words, 1_305, "one thousand three hundred five")]
+    #[case(NumberFormat::Ordinal, 1, "1st")]
+    #[case(NumberFormat::Ordinal, 12, "12th")]
+    #[case(NumberFormat::Ordinal, 23, "23rd")]
+    #[case(NumberFormat::Emoji, 12, "1\u{FE0F}\u{20E3}2\u{FE0F}\u{20E3}")]
+    #[case(NumberFormat::Letters, 1, "A")]
+    #[case(NumberFormat::Letters, 26, "Z")]
+    #[case(NumberFormat::Letters, 28, "AB")]
+    fn test_format(#[case] format: NumberFormat, #[case] number: u64, #[case] expected: &str) {
+        assert_eq!(expected, format.format(number, Locale::English));
+    }
+
+    #[rstest]
+    #[case(NumberFormat::Words, Locale::Swedish, 3, "tre")]
+    #[case(NumberFormat::Words, Locale::Swedish, 21, "tjugoett")]
+    #[case(NumberFormat::Words, Locale::Swedish, 1_305, "ettusentrehundrafem")]
+    #[case(NumberFormat::Words, Locale::Swedish, 2_000_001, "två miljoner ett")]
+    #[case(NumberFormat::Words, Locale::German, 1, "eins")]
+    #[case(NumberFormat::Words, Locale::German, 21, "einundzwanzig")]
+    #[case(NumberFormat::Words, Locale::German, 101, "einhunderteins")]
+    #[case(
+        NumberFormat::Words,
+        Locale::German,
+        1_305,
+        "eintausenddreihundertfünf"
+    )]
+    #[case(
+        NumberFormat::Words,
+        Locale::German,
+        101_000_000,
+        "einhunderteine Millionen"
+    )]
+    #[case(NumberFormat::Ordinal, Locale::Swedish, 1, "1:a")]
+    #[case(NumberFormat::Ordinal, Locale::Swedish, 12, "12:e")]
+    #[case(NumberFormat::Ordinal, Locale::Swedish, 22, "22:a")]
+    #[case(NumberFormat::Ordinal, Locale::German, 7, "7.")]
+    fn test_format_localized(
+        #[case] format: NumberFormat,
+        #[case] locale: Locale,
+        #[case] number: u64,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(expected, format.format(number, locale));
+    }
+
+    #[rstest]
+    #[case("02", NumberFormat::ZeroPadded(2))]
+    #[case("roman", NumberFormat::Roman)]
+    #[case("letter", NumberFormat::Letters)]
+    fn test_parses_spec(#[case] spec: &str, #[case] expected: NumberFormat) {
+        assert_eq!(expected, spec.parse().unwrap());
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("Roman")]
+    #[case("101")]
+    #[case("99999999999999999999999")]
+    fn test_rejects_spec(#[case] spec: &str) {
+        assert!(spec.parse::<NumberFormat>().is_err());
+    }
+}
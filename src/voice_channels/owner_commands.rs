@@ -0,0 +1,884 @@
+use std::time::Duration;
+
+use eyre::{
+    eyre,
+    Result,
+    WrapErr,
+};
+use poise::command;
+use serenity::model::prelude::*;
+use tracing::Instrument;
+#[allow(unused_imports)]
+use tracing::{
+    debug,
+    info,
+    trace,
+    trace_span,
+};
+
+use super::{
+    commands::guild_locale,
+    db::{
+        Child,
+        ChildSetting,
+        Parent,
+    },
+    updater::{
+        ChannelOverrides,
+        MAX_CHANNEL_NAME_LENGTH,
+    },
+};
+use crate::{
+    events::update_child,
+    get_db_handle,
+    get_template_cache,
+    locale::Reply,
+    util::CacheExt,
+    Context,
+    DropExt,
+};
+
+type CommandResult = Result<()>;
+
+/// The permissions needed to see and join a voice channel.
+const ACCESS: Permissions = Permissions::VIEW_CHANNEL.union(Permissions::CONNECT);
+
+/// A change to a permission overwrite. Permissions it doesn't mention keep
+/// their current state.
+#[derive(Debug, Clone, Copy, Default)]
+struct OverwriteChange {
+    allow:   Permissions,
+    deny:    Permissions,
+    /// Permissions that go back to being inherited from the category and roles.
+    inherit: Permissions,
+}
+
+impl OverwriteChange {
+    /// Returns the change that gives `permissions` the state they have in the
+    /// overwrite for `kind` among `source`, inheriting them if it has none.
+    fn restore(
+        permissions: Permissions,
+        source: &[PermissionOverwrite],
+        kind: PermissionOverwriteType,
+    ) -> Self {
+        let (allow, deny) = source
+            .iter()
+            .find(|overwrite| overwrite.kind == kind)
+            .map_or((Permissions::empty(), Permissions::empty()), |overwrite| {
+                (overwrite.allow & permissions, overwrite.deny & permissions)
+            });
+        Self {
+            allow,
+            deny,
+            inherit: permissions - allow - deny,
+        }
+    }
+
+    /// Returns the overwrite for `kind` after applying the change to the one
+    /// among `existing`, if any.
+    fn apply(
+        self,
+        existing: &[PermissionOverwrite],
+        kind: PermissionOverwriteType,
+    ) -> PermissionOverwrite {
+        let (allow, deny) = existing
+            .iter()
+            .find(|overwrite| overwrite.kind == kind)
+            .map_or((Permissions::empty(), Permissions::empty()), |overwrite| {
+                (overwrite.allow, overwrite.deny)
+            });
+        let reset = self.allow | self.deny | self.inherit;
+        PermissionOverwrite {
+            allow: (allow - reset) | self.allow,
+            deny: (deny - reset) | self.deny,
+            kind,
+        }
+    }
+}
+
+/// How long the owner of a child has to have been gone before it can be
+/// claimed.
+const CLAIM_TIMEOUT: Duration = Duration::from_mins(1);
+
+/// Commands the owner of a generated channel can use on it.
+#[allow(clippy::unused_async)]
+#[command(
+    slash_command,
+    category = "voice-channels",
+    guild_only,
+    subcommands(
+        "rename", "limit", "bitrate", "kick", "claim", "lock", "hide", "unlock", "permit", "reject"
+    ),
+    subcommand_required
+)]
+pub(crate) async fn vc(_ctx: Context<'_>) -> CommandResult {
+    Ok(())
+}
+
+/// Renames the channel you own. Leave out the name to follow the template
+/// again.
+#[command(slash_command, guild_only)]
+pub(crate) async fn rename(
+    ctx: Context<'_>,
+    #[description = "The new name of the channel"]
+    #[max_length = 100]
+    name: Option<String>,
+) -> CommandResult {
+    let span = trace_span!("rename span");
+    async move {
+        let Some((parent, child)) = owned_child(ctx).await? else {
+            return Ok(());
+        };
+        if name.as_deref().is_some_and(|name| {
+            name.trim().is_empty()
+                || name.chars().count() > MAX_CHANNEL_NAME_LENGTH
+                || name.chars().any(char::is_control)
+        }) {
+            return refuse(ctx, Reply::ChannelNameInvalid).await;
+        }
+        let overrides = ChannelOverrides {
+            name: name.clone(),
+            ..child.overrides.clone()
+        };
+        apply_overrides(ctx, &parent, child, overrides).await?;
+        match &name {
+            | Some(name) => confirm(ctx, Reply::ChannelRenamed { name }).await,
+            | None => confirm(ctx, Reply::ChannelNameReset).await,
+        }
+    }
+    .instrument(span)
+    .await
+}
+
+/// Sets the user limit of the channel you own. Leave out the limit to follow
+/// the template again.
+#[command(slash_command, guild_only)]
+pub(crate) async fn limit(
+    ctx: Context<'_>,
+    #[description = "The maximum number of members, or 0 for no limit"]
+    #[max = 99]
+    limit: Option<u32>,
+) -> CommandResult {
+    let span = trace_span!("limit span");
+    async move {
+        let Some((parent, child)) = owned_child(ctx).await? else {
+            return Ok(());
+        };
+        let overrides = ChannelOverrides {
+            user_limit: limit,
+            ..child.overrides.clone()
+        };
+        apply_overrides(ctx, &parent, child, overrides).await?;
+        match limit {
+            | Some(limit) =>
+                confirm(
+                    ctx,
+                    Reply::ChannelLimitChanged {
+                        limit: u64::from(limit),
+                    },
+                )
+                .await,
+            | None => confirm(ctx, Reply::ChannelLimitReset).await,
+        }
+    }
+    .instrument(span)
+    .await
+}
+
+/// Sets the bitrate of the channel you own. Leave out the bitrate to follow the
+/// template again.
+#[command(slash_command, guild_only)]
+pub(crate) async fn bitrate(
+    ctx: Context<'_>,
+    #[description = "The bitrate in kbps. Capped by the boost tier of the server"]
+    #[min = 8]
+    #[max = 384]
+    kbps: Option<u32>,
+) -> CommandResult {
+    let span = trace_span!("bitrate span");
+    async move {
+        let Some((parent, child)) = owned_child(ctx).await? else {
+            return Ok(());
+        };
+        let overrides = ChannelOverrides {
+            bitrate: kbps,
+            ..child.overrides.clone()
+        };
+        apply_overrides(ctx, &parent, child, overrides).await?;
+        match kbps {
+            | Some(kbps) =>
+                confirm(
+                    ctx,
+                    Reply::ChannelBitrateChanged {
+                        kbps: u64::from(kbps),
+                    },
+                )
+                .await,
+            | None => confirm(ctx, Reply::ChannelBitrateReset).await,
+        }
+    }
+    .instrument(span)
+    .await
+}
+
+/// Disconnects a member from the channel you own.
+#[command(slash_command, guild_only)]
+pub(crate) async fn kick(
+    ctx: Context<'_>,
+    #[description = "The member to disconnect"] member: Member,
+) -> CommandResult {
+    let span = trace_span!("kick span");
+    async move {
+        let Some((_, child)) = owned_child(ctx).await? else {
+            return Ok(());
+        };
+        if member.user.id == ctx.author().id {
+            return refuse(ctx, Reply::CannotKickSelf).await;
+        }
+        if voice_channel_of(ctx, member.user.id) != Some(child.id) {
+            return refuse(
+                ctx,
+                Reply::MemberNotInChannel {
+                    member: member.display_name(),
+                },
+            )
+            .await;
+        }
+        member
+            .disconnect_from_voice(ctx.serenity_context())
+            .await
+            .wrap_err_with(|| eyre!("Failed to disconnect member!"))?
+            .drop();
+        info!("Kicked {} from child {}!", member.user.id, child.id);
+        confirm(
+            ctx,
+            Reply::MemberKicked {
+                member: member.display_name(),
+            },
+        )
+        .await
+    }
+    .instrument(span)
+    .await
+}
+
+/// Takes over the channel you are connected to once its owner has been gone for
+/// a minute.
+#[command(slash_command, guild_only)]
+pub(crate) async fn claim(ctx: Context<'_>) -> CommandResult {
+    let span = trace_span!("claim span");
+    async move {
+        let Some((parent, mut child)) = connected_child(ctx).await? else {
+            return Ok(());
+        };
+        let author_id = ctx.author().id;
+        if child.owner_id == Some(author_id) {
+            return refuse(ctx, Reply::AlreadyOwner).await;
+        }
+        if child
+            .owner_id
+            .is_some_and(|owner_id| voice_channel_of(ctx, owner_id) == Some(child.id))
+        {
+            return refuse(ctx, Reply::OwnerStillPresent).await;
+        }
+        let claimed = super::db::claim_child(
+            &get_db_handle(ctx.serenity_context()).await,
+            child.id,
+            author_id,
+            CLAIM_TIMEOUT,
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to claim child!"))?;
+        if !claimed {
+            return refuse(
+                ctx,
+                Reply::ClaimTooEarly {
+                    seconds: CLAIM_TIMEOUT.as_secs(),
+                },
+            )
+            .await;
+        }
+        info!("{author_id} claimed child {}!", child.id);
+        // The name may show the owner.
+        child.owner_id = Some(author_id);
+        update_child(
+            ctx.serenity_context(),
+            ctx.guild_id().unwrap(),
+            &parent,
+            &child,
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to update child after claiming it!"))?;
+        confirm(ctx, Reply::ChannelClaimed).await
+    }
+    .instrument(span)
+    .await
+}
+
+/// Stops members other than those in the channel you own from joining it.
+#[command(slash_command, guild_only)]
+pub(crate) async fn lock(ctx: Context<'_>) -> CommandResult {
+    let span = trace_span!("lock span");
+    async move {
+        let Some((_, child)) = owned_child(ctx).await? else {
+            return Ok(());
+        };
+        restrict_to_members(ctx, &child, Permissions::CONNECT).await?;
+        info!("Locked child {}!", child.id);
+        confirm(ctx, Reply::ChannelLocked).await
+    }
+    .instrument(span)
+    .await
+}
+
+/// Hides the channel you own from everyone but the members in it.
+#[command(slash_command, guild_only)]
+pub(crate) async fn hide(ctx: Context<'_>) -> CommandResult {
+    let span = trace_span!("hide span");
+    async move {
+        let Some((_, child)) = owned_child(ctx).await? else {
+            return Ok(());
+        };
+        restrict_to_members(ctx, &child, ACCESS).await?;
+        info!("Hid child {}!", child.id);
+        confirm(ctx, Reply::ChannelHidden).await
+    }
+    .instrument(span)
+    .await
+}
+
+/// Lets everyone who may join the channel yours was created from see and join
+/// it again.
+#[command(slash_command, guild_only)]
+pub(crate) async fn unlock(ctx: Context<'_>) -> CommandResult {
+    let span = trace_span!("unlock span");
+    async move {
+        let Some((parent, child)) = owned_child(ctx).await? else {
+            return Ok(());
+        };
+        let guild_id = ctx.guild_id().unwrap();
+        let cache = &ctx.serenity_context().cache;
+        // The child got the overwrites of the parent when it was created, so
+        // unlocking must not open it up further than the parent is.
+        let parent_overwrites = if parent.inherits(ChildSetting::Permissions) {
+            cache
+                .guild_channel(guild_id, parent.id)?
+                .permission_overwrites
+        } else {
+            Vec::new()
+        };
+        let child_overwrites = cache
+            .guild_channel(guild_id, child.id)?
+            .permission_overwrites;
+        for (kind, change) in unrestrict_changes(&child_overwrites, &parent_overwrites) {
+            edit_overwrite(ctx, child.id, kind, change).await?;
+        }
+        info!("Unlocked child {}!", child.id);
+        confirm(ctx, Reply::ChannelUnlocked).await
+    }
+    .instrument(span)
+    .await
+}
+
+/// Lets a member see and join the channel you own even if it is locked or
+/// hidden.
+#[command(slash_command, guild_only)]
+pub(crate) async fn permit(
+    ctx: Context<'_>,
+    #[description = "The member to let in"] member: Member,
+) -> CommandResult {
+    let span = trace_span!("permit span");
+    async move {
+        let Some((parent, child)) = owned_child(ctx).await? else {
+            return Ok(());
+        };
+        // Owners may only let in members who could join through the parent.
+        let parent_permissions = {
+            let guild = ctx
+                .guild()
+                .ok_or_else(|| eyre!("Guild was missing in cache!"))?;
+            let parent_channel = guild
+                .channels
+                .get(&parent.id)
+                .ok_or_else(|| eyre!("No channel found!"))?;
+            guild.user_permissions_in(parent_channel, &member)
+        };
+        if !parent_permissions.contains(ACCESS) {
+            return refuse(
+                ctx,
+                Reply::PermitDeniedByParent {
+                    member: member.display_name(),
+                },
+            )
+            .await;
+        }
+        edit_overwrite(
+            ctx,
+            child.id,
+            PermissionOverwriteType::Member(member.user.id),
+            OverwriteChange {
+                allow: ACCESS,
+                ..Default::default()
+            },
+        )
+        .await?;
+        info!("Permitted {} in child {}!", member.user.id, child.id);
+        confirm(
+            ctx,
+            Reply::MemberPermitted {
+                member: member.display_name(),
+            },
+        )
+        .await
+    }
+    .instrument(span)
+    .await
+}
+
+/// Stops a member from seeing and joining the channel you own, disconnecting
+/// them if they are in it.
+#[command(slash_command, guild_only)]
+pub(crate) async fn reject(
+    ctx: Context<'_>,
+    #[description = "The member to keep out"] member: Member,
+) -> CommandResult {
+    let span = trace_span!("reject span");
+    async move {
+        let Some((_, child)) = owned_child(ctx).await? else {
+            return Ok(());
+        };
+        if member.user.id == ctx.author().id {
+            return refuse(ctx, Reply::CannotRejectSelf).await;
+        }
+        edit_overwrite(
+            ctx,
+            child.id,
+            PermissionOverwriteType::Member(member.user.id),
+            OverwriteChange {
+                deny: ACCESS,
+                ..Default::default()
+            },
+        )
+        .await?;
+        if voice_channel_of(ctx, member.user.id) == Some(child.id) {
+            member
+                .disconnect_from_voice(ctx.serenity_context())
+                .await
+                .wrap_err_with(|| eyre!("Failed to disconnect member!"))?
+                .drop();
+        }
+        info!("Rejected {} from child {}!", member.user.id, child.id);
+        confirm(
+            ctx,
+            Reply::MemberRejected {
+                member: member.display_name(),
+            },
+        )
+        .await
+    }
+    .instrument(span)
+    .await
+}
+
+/// Denies `permissions` to everyone on a child, but allows them to the owner
+/// and the members connected to it so that they aren't locked out.
+async fn restrict_to_members(
+    ctx: Context<'_>,
+    child: &Child,
+    permissions: Permissions,
+) -> Result<()> {
+    let guild_id = ctx.guild_id().unwrap();
+    let existing = ctx
+        .serenity_context()
+        .cache
+        .guild_channel(guild_id, child.id)?
+        .permission_overwrites;
+    let mut members = ctx
+        .guild()
+        .map(|guild| {
+            guild
+                .voice_states
+                .values()
+                .filter(|state| state.channel_id == Some(child.id))
+                .map(|state| state.user_id)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    members.extend(child.owner_id);
+    members.sort_unstable();
+    members.dedup();
+    for (kind, change) in
+        restrict_changes(&existing, guild_id.everyone_role(), &members, permissions)
+    {
+        edit_overwrite(ctx, child.id, kind, change).await?;
+    }
+    Ok(())
+}
+
+/// Returns the changes to the overwrites of a child that deny `permissions` to
+/// everyone but `members`. Roles stop allowing them too, as the allow of a role
+/// overrides the deny of `@everyone`. Members come first so that they aren't
+/// disconnected in between.
+fn restrict_changes(
+    existing: &[PermissionOverwrite],
+    everyone: RoleId,
+    members: &[UserId],
+    permissions: Permissions,
+) -> Vec<(PermissionOverwriteType, OverwriteChange)> {
+    let member_changes = members.iter().map(|&user_id| {
+        (
+            PermissionOverwriteType::Member(user_id),
+            OverwriteChange {
+                allow: permissions,
+                ..Default::default()
+            },
+        )
+    });
+    let role_changes = existing
+        .iter()
+        .filter(|overwrite| {
+            matches!(overwrite.kind, PermissionOverwriteType::Role(role_id) if role_id != everyone)
+                && overwrite.allow.intersects(permissions)
+        })
+        .map(|overwrite| {
+            (
+                overwrite.kind,
+                OverwriteChange {
+                    inherit: overwrite.allow & permissions,
+                    ..Default::default()
+                },
+            )
+        });
+    member_changes
+        .chain(role_changes)
+        .chain([(
+            PermissionOverwriteType::Role(everyone),
+            OverwriteChange {
+                deny: permissions,
+                ..Default::default()
+            },
+        )])
+        .collect()
+}
+
+/// Returns the changes to the overwrites of a child that give `@everyone` and
+/// every role the access they have on the parent again, and take back the
+/// access members were allowed beyond it, be it by locking or permitting them.
+/// Members that were rejected stay rejected.
+fn unrestrict_changes(
+    existing: &[PermissionOverwrite],
+    parent: &[PermissionOverwrite],
+) -> Vec<(PermissionOverwriteType, OverwriteChange)> {
+    let mut changes: Vec<(PermissionOverwriteType, OverwriteChange)> = Vec::new();
+    for overwrite in existing.iter().chain(parent) {
+        let kind = overwrite.kind;
+        if changes.iter().any(|&(changed, _)| changed == kind) {
+            continue;
+        }
+        let restored = OverwriteChange::restore(ACCESS, parent, kind);
+        let change = if let PermissionOverwriteType::Member(_) = kind {
+            let rejected = existing
+                .iter()
+                .find(|overwrite| overwrite.kind == kind)
+                .map_or(Permissions::empty(), |overwrite| overwrite.deny & ACCESS);
+            let allow = restored.allow - rejected;
+            OverwriteChange {
+                allow,
+                inherit: ACCESS - allow - rejected,
+                ..Default::default()
+            }
+        } else {
+            restored
+        };
+        changes.push((kind, change));
+    }
+    changes
+}
+
+/// Applies a change to the permission overwrite of a role or member on a child.
+async fn edit_overwrite(
+    ctx: Context<'_>,
+    child_id: ChannelId,
+    kind: PermissionOverwriteType,
+    change: OverwriteChange,
+) -> Result<()> {
+    let existing = ctx
+        .serenity_context()
+        .cache
+        .guild_channel(ctx.guild_id().unwrap(), child_id)?
+        .permission_overwrites;
+    child_id
+        .create_permission(ctx.http(), change.apply(&existing, kind))
+        .await
+        .wrap_err_with(|| eyre!("Failed to edit permission overwrite of channel {child_id}!"))
+}
+
+/// Returns the voice channel a member is connected to according to the cache.
+fn voice_channel_of(ctx: Context<'_>, user_id: UserId) -> Option<ChannelId> {
+    ctx.guild()?
+        .voice_states
+        .get(&user_id)
+        .and_then(|state| state.channel_id)
+}
+
+/// Returns the child the author is connected to along with its parent. Tells
+/// the author that they aren't connected to one and returns `None` otherwise.
+async fn connected_child(ctx: Context<'_>) -> Result<Option<(Parent, Child)>> {
+    let Some(channel_id) = voice_channel_of(ctx, ctx.author().id) else {
+        refuse(ctx, Reply::NotInChild).await?;
+        return Ok(None);
+    };
+    let Some((parent, children)) = super::db::get_all_children_of_parent(
+        &get_db_handle(ctx.serenity_context()).await,
+        &*get_template_cache(ctx.serenity_context()).await,
+        ctx.guild_id().unwrap(),
+        &[channel_id.get() as i64],
+    )
+    .await
+    .wrap_err_with(|| eyre!("Retrieving voice channels failed!"))?
+    else {
+        refuse(ctx, Reply::NotInChild).await?;
+        return Ok(None);
+    };
+    let Some(child) = children
+        .get(&Child {
+            id: channel_id,
+            ..Default::default()
+        })
+        .cloned()
+    else {
+        refuse(ctx, Reply::NotInChild).await?;
+        return Ok(None);
+    };
+    Ok(Some((parent, child)))
+}
+
+/// Returns the child the author is connected to along with its parent if the
+/// author owns it. Tells the author why not and returns `None` otherwise.
+async fn owned_child(ctx: Context<'_>) -> Result<Option<(Parent, Child)>> {
+    let Some((parent, child)) = connected_child(ctx).await? else {
+        return Ok(None);
+    };
+    if child.owner_id != Some(ctx.author().id) {
+        refuse(ctx, Reply::NotChannelOwner).await?;
+        return Ok(None);
+    }
+    Ok(Some((parent, child)))
+}
+
+/// Stores the new overrides of a child and applies them to the channel.
+async fn apply_overrides(
+    ctx: Context<'_>,
+    parent: &Parent,
+    mut child: Child,
+    overrides: ChannelOverrides,
+) -> Result<()> {
+    super::db::set_child_overrides(
+        &get_db_handle(ctx.serenity_context()).await,
+        child.id,
+        &overrides,
+    )
+    .await
+    .wrap_err_with(|| eyre!("Failed to store overrides!"))?;
+    info!("Set overrides of child {} to {overrides:?}!", child.id);
+    child.overrides = overrides;
+    update_child(
+        ctx.serenity_context(),
+        ctx.guild_id().unwrap(),
+        parent,
+        &child,
+    )
+    .await
+    .wrap_err_with(|| eyre!("Failed to update child after setting overrides!"))
+}
+
+/// Tells the author that their command succeeded.
+async fn confirm(ctx: Context<'_>, reply: Reply<'_>) -> CommandResult {
+    let locale = guild_locale(ctx).await?;
+    ctx.channel_id()
+        .say(
+            &ctx.http(),
+            format!("{} - {}", ctx.author().mention(), reply.localize(locale)),
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to send message!"))?
+        .drop();
+    Ok(())
+}
+
+/// Tells the author why their command was rejected.
+async fn refuse(ctx: Context<'_>, reply: Reply<'_>) -> CommandResult {
+    let locale = guild_locale(ctx).await?;
+    ctx.channel_id()
+        .say(
+            &ctx.http(),
+            format!("{}: {}", ctx.author().mention(), reply.localize(locale)),
+        )
+        .await
+        .wrap_err_with(|| eyre!("Failed to send message!"))?
+        .drop();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+
+    const MEMBER: PermissionOverwriteType = PermissionOverwriteType::Member(UserId::new(1));
+    const EVERYONE: PermissionOverwriteType = PermissionOverwriteType::Role(RoleId::new(2));
+    const ROLE: PermissionOverwriteType = PermissionOverwriteType::Role(RoleId::new(3));
+
+    fn overwrite(
+        kind: PermissionOverwriteType,
+        allow: Permissions,
+        deny: Permissions,
+    ) -> PermissionOverwrite {
+        PermissionOverwrite { allow, deny, kind }
+    }
+
+    /// Applies changes the way `edit_overwrite` does, one after the other.
+    fn apply_all(
+        mut overwrites: Vec<PermissionOverwrite>,
+        changes: Vec<(PermissionOverwriteType, OverwriteChange)>,
+    ) -> Vec<PermissionOverwrite> {
+        for (kind, change) in changes {
+            let applied = change.apply(&overwrites, kind);
+            match overwrites
+                .iter_mut()
+                .find(|overwrite| overwrite.kind == kind)
+            {
+                | Some(overwrite) => *overwrite = applied,
+                | None => overwrites.push(applied),
+            }
+        }
+        overwrites
+    }
+
+    /// The overwrites of a parent only a role may see and join.
+    fn role_restricted() -> Vec<PermissionOverwrite> {
+        vec![
+            overwrite(EVERYONE, Permissions::empty(), ACCESS),
+            overwrite(ROLE, ACCESS, Permissions::empty()),
+        ]
+    }
+
+    #[rstest]
+    // Locking leaves the role able to see the child, hiding doesn't.
+    #[case(Permissions::CONNECT, Permissions::VIEW_CHANNEL)]
+    #[case(ACCESS, Permissions::empty())]
+    fn test_restrict_overrides_allowed_role(
+        #[case] permissions: Permissions,
+        #[case] expected_role_allow: Permissions,
+    ) {
+        assert_eq!(
+            vec![
+                overwrite(EVERYONE, Permissions::empty(), ACCESS),
+                overwrite(ROLE, expected_role_allow, Permissions::empty()),
+                overwrite(MEMBER, permissions, Permissions::empty()),
+            ],
+            apply_all(
+                role_restricted(),
+                restrict_changes(
+                    &role_restricted(),
+                    RoleId::new(2),
+                    &[UserId::new(1)],
+                    permissions
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_unrestrict_restores_parent_roles_and_members() {
+        // The child was hidden with the member in it, and another member was
+        // rejected.
+        let rejected = PermissionOverwriteType::Member(UserId::new(4));
+        let child = vec![
+            overwrite(EVERYONE, Permissions::empty(), ACCESS),
+            overwrite(ROLE, Permissions::empty(), Permissions::empty()),
+            overwrite(MEMBER, ACCESS, Permissions::empty()),
+            overwrite(rejected, Permissions::empty(), ACCESS),
+        ];
+        let parent = role_restricted();
+        assert_eq!(
+            vec![
+                overwrite(EVERYONE, Permissions::empty(), ACCESS),
+                overwrite(ROLE, ACCESS, Permissions::empty()),
+                overwrite(MEMBER, Permissions::empty(), Permissions::empty()),
+                overwrite(rejected, Permissions::empty(), ACCESS),
+            ],
+            apply_all(child.clone(), unrestrict_changes(&child, &parent))
+        );
+    }
+
+    #[rstest]
+    // A child of an open parent is opened to everyone.
+    #[case(&[], Permissions::empty(), Permissions::empty())]
+    #[case(&[(Permissions::SPEAK, Permissions::empty())], Permissions::empty(), Permissions::empty())]
+    // A child of a restricted parent stays as restricted as the parent.
+    #[case(&[(Permissions::empty(), Permissions::VIEW_CHANNEL)], Permissions::empty(), Permissions::VIEW_CHANNEL)]
+    #[case(&[(Permissions::empty(), ACCESS | Permissions::SPEAK)], Permissions::empty(), ACCESS)]
+    #[case(&[(Permissions::CONNECT, Permissions::VIEW_CHANNEL)], Permissions::CONNECT, Permissions::VIEW_CHANNEL)]
+    fn test_unlock_restores_parent_overwrite(
+        #[case] parent: &[(Permissions, Permissions)],
+        #[case] expected_allow: Permissions,
+        #[case] expected_deny: Permissions,
+    ) {
+        let parent = parent
+            .iter()
+            .map(|&(allow, deny)| PermissionOverwrite {
+                allow,
+                deny,
+                kind: EVERYONE,
+            })
+            .collect::<Vec<_>>();
+        // The child was locked and hidden, on top of what it got from the
+        // parent.
+        let child = [PermissionOverwrite {
+            allow: Permissions::SPEAK,
+            deny:  ACCESS,
+            kind:  EVERYONE,
+        }];
+        assert_eq!(
+            PermissionOverwrite {
+                allow: expected_allow | Permissions::SPEAK,
+                deny:  expected_deny,
+                kind:  EVERYONE,
+            },
+            OverwriteChange::restore(ACCESS, &parent, EVERYONE).apply(&child, EVERYONE)
+        );
+    }
+
+    #[rstest]
+    #[case(&[], OverwriteChange { deny: Permissions::CONNECT, ..Default::default() }, Permissions::empty(), Permissions::CONNECT)]
+    #[case(&[(Permissions::SPEAK, Permissions::empty())], OverwriteChange { deny: Permissions::CONNECT, ..Default::default() }, Permissions::SPEAK, Permissions::CONNECT)]
+    #[case(&[(Permissions::CONNECT, Permissions::SPEAK)], OverwriteChange { deny: ACCESS, ..Default::default() }, Permissions::empty(), ACCESS | Permissions::SPEAK)]
+    #[case(&[(Permissions::empty(), ACCESS)], OverwriteChange { allow: ACCESS, ..Default::default() }, ACCESS, Permissions::empty())]
+    #[case(&[(Permissions::SPEAK, ACCESS)], OverwriteChange { inherit: ACCESS, ..Default::default() }, Permissions::SPEAK, Permissions::empty())]
+    fn test_apply_overwrite_change(
+        #[case] existing: &[(Permissions, Permissions)],
+        #[case] change: OverwriteChange,
+        #[case] expected_allow: Permissions,
+        #[case] expected_deny: Permissions,
+    ) {
+        let existing = existing
+            .iter()
+            .map(|&(allow, deny)| PermissionOverwrite {
+                allow,
+                deny,
+                kind: MEMBER,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            PermissionOverwrite {
+                allow: expected_allow,
+                deny:  expected_deny,
+                kind:  MEMBER,
+            },
+            change.apply(&existing, MEMBER)
+        );
+    }
+}
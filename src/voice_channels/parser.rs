@@ -1,24 +1,99 @@
-use eyre::{
-    eyre,
-    Result,
-    WrapErr,
+use std::{
+    error::Error,
+    fmt::{
+        self,
+        Display,
+        Formatter,
+        Write,
+    },
+    ops::Range,
 };
 
+use chrono::format::{
+    Item,
+    StrftimeItems,
+};
+
+use super::{
+    expr::{
+        BinaryOp,
+        Expr,
+        Operand,
+    },
+    format::NumberFormat,
+};
+use crate::DropExt;
+
+const DIRECTIVES: &[&str] = &[
+    "`owner`",
+    "`owner_nick`",
+    "`game`",
+    "`users`",
+    "`cap`",
+    "`time`",
+    "`date`",
+    "`weekday`",
+    "`pool`",
+    "`rand`",
+];
+const CONDITIONS: &[&str] = &["`full`", "`empty`", "`cap`", "`game`"];
+const CONTENT: &[&str] = &[
+    "`#`",
+    "`%`",
+    "`?`",
+    "`$`",
+    "`(`",
+    "a number",
+    "a directive name",
+];
+const OPERANDS: &[&str] = &["`#`", "`%`", "`users`", "`cap`", "a number", "`(`"];
+const OPERATORS: &[&str] = &["`+`", "`-`", "`*`", "`/`", "`:`", "`}`"];
+const BINARY_OPERATORS: &[&str] = &["`+`", "`-`", "`*`", "`/`"];
+const CLOSING_PAREN: &[&str] = &["`+`", "`-`", "`*`", "`/`", "`)`"];
+/// Directives that are operands and may therefore be used in expressions.
+const OPERAND_NAMES: &[&str] = &["users", "cap"];
+const TIME_FORMATS: &[&str] = &["a strftime format such as `%H:%M`"];
+const FORMAT_SPECS: &[&str] = &[
+    "a padding width",
+    "`roman`",
+    "`words`",
+    "`ordinal`",
+    "`emoji`",
+    "`letters`",
+];
+
+type Result<T> = std::result::Result<T, TemplateError>;
+
+/// A token of an arithmetic expression inside braces.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum Token<'a> {
+    Hash,
+    Percent,
+    Number(&'a str),
+    Ident(&'a str),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    OpenParen,
+    CloseParen,
+    /// A format specifier, the closing brace or the end of input, all of
+    /// which end an expression.
+    End,
+    /// A character that can't appear in an expression.
+    Unknown,
+}
+
 struct Parser<'a> {
-    input:       &'a str,
-    current_idx: usize,
-    current_col: usize,
-    current_row: usize,
-    parts:       Vec<TemplatePart>,
+    input:        &'a str,
+    current_idx:  usize,
+    /// The name pool referenced so far, since a template may only use one.
+    pool:         Option<&'a str>,
+    /// The number of random picks parsed so far.
+    random_picks: usize,
 }
 
 impl<'a> Parser<'a> {
-    fn starts_with(&self, s: &str) -> bool {
-        self.input
-            .get(self.current_idx..)
-            .map_or(false, |ss| ss.starts_with(s))
-    }
-
     fn current_byte(&self) -> Option<u8> {
         self.input.as_bytes().get(self.current_idx).copied()
     }
@@ -30,162 +105,953 @@ impl<'a> Parser<'a> {
     }
 
     fn advance(&mut self) {
-        let Some(c) = self.current_char() else {
-            return;
-        };
-
-        if let Some(b'\n') = self.current_byte() {
-            self.current_col = 1;
-            self.current_row += c.len_utf8();
-        } else {
-            self.current_col += 1;
+        if let Some(c) = self.current_char() {
+            self.current_idx += c.len_utf8();
         }
-        self.current_idx += c.len_utf8();
     }
 
     fn new(input: &'a str) -> Self {
         Self {
             input,
             current_idx: 0,
-            current_col: 1,
-            current_row: 1,
-            parts: vec![],
+            pool: None,
+            random_picks: 0,
         }
     }
 
+    fn error(
+        &self,
+        span: Range<usize>,
+        kind: TemplateErrorKind,
+        expected: &'static [&'static str],
+    ) -> TemplateError {
+        TemplateError {
+            source: self.input.to_owned(),
+            span,
+            kind,
+            expected,
+        }
+    }
+
+    /// Creates an error pointing at the current character, which was not one
+    /// of the expected tokens.
+    fn unexpected(&self, expected: &'static [&'static str]) -> TemplateError {
+        match self.current_char() {
+            | Some(c) => self.error(
+                self.current_idx..self.current_idx + c.len_utf8(),
+                TemplateErrorKind::UnexpectedChar(c),
+                expected,
+            ),
+            | None => self.error(
+                self.current_idx..self.current_idx,
+                TemplateErrorKind::UnexpectedEnd,
+                expected,
+            ),
+        }
+    }
+
+    /// Returns true if the input continues with the given byte twice in a
+    /// row, which escapes it.
+    fn starts_with_escaped(&self, b: u8) -> bool {
+        self.input
+            .as_bytes()
+            .get(self.current_idx..self.current_idx + 2)
+            == Some(&[b, b])
+    }
+
     fn parse(mut self) -> Result<Template> {
-        while self.current_idx < self.input.len() {
-            if self.current_byte() == Some(b'{') {
-                let res = self.parse_braces().wrap_err_with(|| {
-                    eyre!(
-                        "Failed to parse braces at {}:{}",
-                        self.current_col,
-                        self.current_row
-                    )
-                })?;
-                self.parts.push(res);
+        let parts = self.parse_parts(&[])?;
+        Ok(Template { parts })
+    }
+
+    /// Parses parts until the end of input or an unescaped terminator, which
+    /// is left unconsumed.
+    fn parse_parts(&mut self, terminators: &[u8]) -> Result<Vec<TemplatePart>> {
+        let mut parts = vec![];
+        while let Some(b) = self.current_byte() {
+            if terminators.contains(&b) && !self.starts_with_escaped(b) {
+                break;
+            }
+            if b == b'{' && !self.starts_with_escaped(b) {
+                parts.push(self.parse_braces()?);
             } else {
-                let res = self.parse_string().wrap_err_with(|| {
-                    eyre!(
-                        "Failed to parse string at {}:{}",
-                        self.current_col,
-                        self.current_row
-                    )
-                })?;
-                self.parts.push(res);
+                parts.push(self.parse_string(terminators));
             }
         }
-        Ok(Template { parts: self.parts })
+        Ok(parts)
     }
 
-    fn parse_string(&mut self) -> Result<TemplatePart> {
+    fn parse_string(&mut self, terminators: &[u8]) -> TemplatePart {
         let start_idx = self.current_idx;
         let mut contents = String::new();
         loop {
-            while self.starts_with("{{") {
-                self.advance();
-                self.advance();
-                contents.push('{');
-                continue;
-            }
-            while self.starts_with("}}") {
-                self.advance();
-                self.advance();
-                contents.push('}');
-                continue;
+            if let Some(b) = self
+                .current_byte()
+                .filter(|b| matches!(b, b'{' | b'}') || terminators.contains(b))
+            {
+                if self.starts_with_escaped(b) {
+                    self.advance();
+                    self.advance();
+                    contents.push(char::from(b));
+                    continue;
+                }
             }
             let Some(c) = self.current_char() else { break };
-            if c == '{' {
+            if c == '{' || u8::try_from(c).is_ok_and(|b| terminators.contains(&b)) {
                 break;
             }
-            // println!("{} - {}:{}", self.current_idx, self.current_col, self.current_row);
             contents.push(c);
             self.advance();
         }
-
-        if start_idx == self.current_idx {
-            return Err(eyre!(
-                "Unexpectedly parsed empty string, this should never happen! At {}:{} with \
-                 current char: {}",
-                self.current_col,
-                self.current_row,
-                self.current_char().unwrap_or('\0')
-            ));
-        }
-        Ok(TemplatePart::String(contents))
+        debug_assert_ne!(start_idx, self.current_idx, "Parsed an empty string");
+        TemplatePart::String(contents)
     }
 
     fn parse_braces(&mut self) -> Result<TemplatePart> {
         assert_eq!(self.current_byte(), Some(b'{'));
         self.advance();
-        let content = self.parse_template_content().wrap_err_with(|| {
-            eyre!(
-                "Failed to parse content of template at {}:{}",
-                self.current_col,
-                self.current_row
-            )
-        })?;
+        let content = self.parse_template_content()?;
 
         if self.current_byte() != Some(b'}') {
-            return Err(eyre!(
-                "Expected '}}' at {}:{}, but found '{}'",
-                self.current_col,
-                self.current_row,
-                self.current_char().unwrap_or('\0')
-            ));
+            return Err(self.unexpected(&["`}`"]));
         }
         self.advance();
         Ok(content)
     }
 
     fn parse_template_content(&mut self) -> Result<TemplatePart> {
-        let ret = Ok(
-            match self.current_byte().ok_or_else(|| {
-                eyre!(
-                    "Unexpected end of input at {}:{}. Reached end of input while trying to parse \
-                     template content.",
-                    self.current_col,
-                    self.current_row
+        // Expressions may be padded with whitespace but directives may not, so
+        // whitespace in front of a directive is the error rather than the
+        // directive, which would otherwise be mistaken for an unknown operand.
+        let whitespace = self.input[self.current_idx..]
+            .bytes()
+            .take_while(u8::is_ascii_whitespace)
+            .count();
+        let content_idx = self.current_idx + whitespace;
+        if whitespace > 0 && self.starts_directive(content_idx) {
+            return Err(self.error(
+                self.current_idx..content_idx,
+                TemplateErrorKind::UnexpectedWhitespace,
+                CONTENT,
+            ));
+        }
+        match self.current_byte() {
+            | Some(b'?') => return self.parse_conditional(),
+            | Some(b'$') => return self.parse_variable(),
+            | Some(c)
+                if c.is_ascii_alphabetic() && !OPERAND_NAMES.contains(&self.peek_identifier()) =>
+                return self.parse_directive(),
+            | _ => {},
+        }
+        if !matches!(
+            self.peek_token().1,
+            Token::Hash
+                | Token::Percent
+                | Token::Number(_)
+                | Token::Ident(_)
+                | Token::Minus
+                | Token::OpenParen
+        ) {
+            return Err(self.unexpected(CONTENT));
+        }
+        let expr = self.parse_sum()?;
+        if self.peek_token().1 != Token::End {
+            return Err(self.unexpected(OPERATORS));
+        }
+        let format = self.parse_number_format()?;
+        // Lone operands keep their own parts so that they render as before.
+        Ok(match expr {
+            | Expr::Operand(Operand::ChannelNumber) => TemplatePart::ChannelNumber(format),
+            | Expr::Operand(Operand::ChildrenInTotal) => TemplatePart::ChildrenInTotal(format),
+            | Expr::Operand(Operand::Users) => TemplatePart::Users(format),
+            | Expr::Operand(Operand::Capacity) => TemplatePart::Capacity(format),
+            | expr => TemplatePart::Expression { expr, format },
+        })
+    }
+
+    /// Skips whitespace and returns the next token of an expression along with
+    /// its span, without consuming it.
+    fn peek_token(&mut self) -> (Range<usize>, Token<'a>) {
+        while self.current_byte().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.advance();
+        }
+        let start_idx = self.current_idx;
+        let rest = &self.input.as_bytes()[start_idx..];
+        let length_while = |pred: fn(&u8) -> bool| rest.iter().take_while(|&b| pred(b)).count();
+        let (length, token) = match rest.first() {
+            | None | Some(b':' | b'}') => (0, Token::End),
+            | Some(b'#') => (1, Token::Hash),
+            | Some(b'%') => (1, Token::Percent),
+            | Some(b'+') => (1, Token::Plus),
+            | Some(b'-') => (1, Token::Minus),
+            | Some(b'*') => (1, Token::Star),
+            | Some(b'/') => (1, Token::Slash),
+            | Some(b'(') => (1, Token::OpenParen),
+            | Some(b')') => (1, Token::CloseParen),
+            | Some(b) if b.is_ascii_digit() => {
+                let length = length_while(u8::is_ascii_digit);
+                (
+                    length,
+                    Token::Number(&self.input[start_idx..start_idx + length]),
                 )
-            })? {
-                | b'#' => TemplatePart::ChannelNumber,
-                | b'%' => TemplatePart::ChildrenInTotal,
-                | _ =>
-                    return Err(eyre!(
-                        "Invalid template content at {}:{}. Expected one of '#' or '%' but found \
-                         '{}'",
-                        self.current_col,
-                        self.current_row,
-                        self.current_char().unwrap_or('\0')
-                    )),
             },
-        );
+            | Some(b) if b.is_ascii_alphabetic() => {
+                let length = length_while(|b| b.is_ascii_alphanumeric() || *b == b'_');
+                (
+                    length,
+                    Token::Ident(&self.input[start_idx..start_idx + length]),
+                )
+            },
+            | Some(_) => (0, Token::Unknown),
+        };
+        (start_idx..start_idx + length, token)
+    }
+
+    /// Parses a sum of products, e.g. `# + 2 * 3`.
+    fn parse_sum(&mut self) -> Result<Expr> {
+        let mut lhs = self.parse_product()?;
+        loop {
+            let (span, token) = self.peek_token();
+            let op = match token {
+                | Token::Plus => BinaryOp::Add,
+                | Token::Minus => BinaryOp::Sub,
+                | _ => return Ok(lhs),
+            };
+            self.current_idx = span.end;
+            let rhs = self.parse_product()?;
+            lhs = Expr::binary(op, lhs, rhs);
+        }
+    }
+
+    /// Parses a product of unary expressions, e.g. `2 * -#`.
+    fn parse_product(&mut self) -> Result<Expr> {
+        let mut lhs = self.parse_unary()?;
+        loop {
+            let (span, token) = self.peek_token();
+            let op = match token {
+                | Token::Star => BinaryOp::Mul,
+                | Token::Slash => BinaryOp::Div,
+                | _ => return Ok(lhs),
+            };
+            self.current_idx = span.end;
+            let rhs_start = self.peek_token().0.start;
+            let rhs = self.parse_unary()?;
+            if op == BinaryOp::Div && rhs == Expr::Operand(Operand::Literal(0)) {
+                return Err(self.error(
+                    rhs_start..self.current_idx,
+                    TemplateErrorKind::DivisionByZero,
+                    &[],
+                ));
+            }
+            lhs = Expr::binary(op, lhs, rhs);
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        let (span, token) = self.peek_token();
+        if token == Token::Minus {
+            self.current_idx = span.end;
+            return Ok(Expr::Negate(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    /// Parses an operand or a parenthesized expression.
+    fn parse_primary(&mut self) -> Result<Expr> {
+        let (span, token) = self.peek_token();
+        let operand = match token {
+            | Token::Hash => Operand::ChannelNumber,
+            | Token::Percent => Operand::ChildrenInTotal,
+            | Token::Ident("users") => Operand::Users,
+            | Token::Ident("cap") => Operand::Capacity,
+            | Token::Ident(name) =>
+                return Err(self.error(
+                    span,
+                    TemplateErrorKind::UnknownDirective(name.to_owned()),
+                    OPERANDS,
+                )),
+            | Token::Number(digits) => match digits.parse() {
+                | Ok(value) => Operand::Literal(value),
+                | Err(_) => return Err(self.error(span, TemplateErrorKind::NumberTooLarge, &[])),
+            },
+            | Token::OpenParen => {
+                self.current_idx = span.end;
+                let expr = self.parse_sum()?;
+                let (span, token) = self.peek_token();
+                if token != Token::CloseParen {
+                    return Err(self.unexpected(CLOSING_PAREN));
+                }
+                self.current_idx = span.end;
+                return Ok(expr);
+            },
+            | _ => return Err(self.unexpected(OPERANDS)),
+        };
+        self.current_idx = span.end;
+        Ok(Expr::Operand(operand))
+    }
+
+    /// Parses the optional `:spec` suffix of a numeric directive.
+    fn parse_number_format(&mut self) -> Result<NumberFormat> {
+        if self.current_byte() != Some(b':') {
+            return Ok(NumberFormat::Plain);
+        }
         self.advance();
-        ret
+        let (span, spec) = self.parse_identifier();
+        if spec.is_empty() {
+            return Err(self.unexpected(FORMAT_SPECS));
+        }
+        spec.parse().map_err(|err: eyre::Report| {
+            self.error(
+                span,
+                TemplateErrorKind::InvalidFormatSpec(err.to_string()),
+                FORMAT_SPECS,
+            )
+        })
+    }
+
+    fn peek_identifier(&self) -> &'a str {
+        self.identifier_at(self.current_idx)
+    }
+
+    fn identifier_at(&self, idx: usize) -> &'a str {
+        let rest = &self.input[idx..];
+        let length = rest
+            .bytes()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == b'_')
+            .count();
+        &rest[..length]
+    }
+
+    /// Returns true if a conditional, a variable or a directive other than an
+    /// operand starts at the given index.
+    fn starts_directive(&self, idx: usize) -> bool {
+        match self.input.as_bytes().get(idx) {
+            | Some(b'?' | b'$') => true,
+            | Some(c) =>
+                c.is_ascii_alphabetic() && !OPERAND_NAMES.contains(&self.identifier_at(idx)),
+            | None => false,
+        }
+    }
+
+    fn parse_identifier(&mut self) -> (Range<usize>, &'a str) {
+        let start_idx = self.current_idx;
+        while self
+            .current_byte()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_')
+        {
+            self.advance();
+        }
+        (
+            start_idx..self.current_idx,
+            &self.input[start_idx..self.current_idx],
+        )
+    }
+
+    fn parse_directive(&mut self) -> Result<TemplatePart> {
+        Ok(match self.parse_identifier() {
+            | (_, "owner") => TemplatePart::OwnerName,
+            | (_, "owner_nick") => TemplatePart::OwnerNick,
+            | (_, "game") => TemplatePart::Game {
+                fallback: self.parse_fallback()?,
+            },
+            | (_, "time") => TemplatePart::Time {
+                format: self.parse_time_format(DEFAULT_TIME_FORMAT)?,
+            },
+            | (_, "date") => TemplatePart::Date {
+                format: self.parse_time_format(DEFAULT_DATE_FORMAT)?,
+            },
+            | (_, "weekday") => TemplatePart::Weekday,
+            | (_, "pool") => self.parse_pool()?,
+            | (_, "rand") => self.parse_random()?,
+            | (span, name) =>
+                return Err(self.error(
+                    span,
+                    TemplateErrorKind::UnknownDirective(name.to_owned()),
+                    DIRECTIVES,
+                )),
+        })
+    }
+
+    /// Parses the `:name` suffix of a pool directive.
+    fn parse_pool(&mut self) -> Result<TemplatePart> {
+        if self.current_byte() != Some(b':') {
+            return Err(self.unexpected(&["`:`"]));
+        }
+        self.advance();
+        let (span, name) = match self.parse_identifier() {
+            | (_, "") => return Err(self.unexpected(&["a pool name"])),
+            | ident => ident,
+        };
+        match self.pool {
+            | Some(pool) if pool != name =>
+                return Err(self.error(span, TemplateErrorKind::MultiplePools, &[])),
+            | _ => self.pool = Some(name),
+        }
+        Ok(TemplatePart::Pool(name.to_owned()))
+    }
+
+    /// Parses the `:option|option...` suffix of a random pick. Options run
+    /// until the next pipe or the closing brace and may not contain braces.
+    fn parse_random(&mut self) -> Result<TemplatePart> {
+        if self.current_byte() != Some(b':') {
+            return Err(self.unexpected(&["`:`"]));
+        }
+        let mut options = vec![];
+        while matches!(self.current_byte(), Some(b':' | b'|')) {
+            self.advance();
+            let start_idx = self.current_idx;
+            loop {
+                match self.current_char() {
+                    | Some('|' | '}') => break,
+                    | Some('{') | None =>
+                        return Err(self.unexpected(&["`|`", "`}`", "option text"])),
+                    | Some(_) => self.advance(),
+                }
+            }
+            options.push(self.input[start_idx..self.current_idx].to_owned());
+        }
+        let index = self.random_picks;
+        self.random_picks += 1;
+        Ok(TemplatePart::Random { index, options })
+    }
+
+    /// Parses a reference to a guild variable of the form `$name`.
+    fn parse_variable(&mut self) -> Result<TemplatePart> {
+        assert_eq!(self.current_byte(), Some(b'$'));
+        self.advance();
+        match self.parse_identifier() {
+            | (_, "") => Err(self.unexpected(&["a variable name"])),
+            | (_, name) => Ok(TemplatePart::Variable(name.to_owned())),
+        }
+    }
+
+    /// Parses a conditional of the form `?condition:then|otherwise`, where the
+    /// condition may be negated with `!` and `|otherwise` is optional.
+    fn parse_conditional(&mut self) -> Result<TemplatePart> {
+        assert_eq!(self.current_byte(), Some(b'?'));
+        self.advance();
+        let negated = self.current_byte() == Some(b'!');
+        if negated {
+            self.advance();
+        }
+        let condition = match self.parse_identifier() {
+            | (_, "full") => Condition::Full,
+            | (_, "empty") => Condition::Empty,
+            | (_, "cap") => Condition::Capped,
+            | (_, "game") => Condition::Playing,
+            | (_, "") => return Err(self.unexpected(CONDITIONS)),
+            | (span, name) =>
+                return Err(self.error(
+                    span,
+                    TemplateErrorKind::UnknownCondition(name.to_owned()),
+                    CONDITIONS,
+                )),
+        };
+        if self.current_byte() != Some(b':') {
+            return Err(self.unexpected(&["`:`"]));
+        }
+        self.advance();
+        let then = self.parse_parts(b"|}")?;
+        let otherwise = if self.current_byte() == Some(b'|') {
+            self.advance();
+            self.parse_parts(b"|}")?
+        } else {
+            vec![]
+        };
+        Ok(TemplatePart::Conditional {
+            condition,
+            negated,
+            then,
+            otherwise,
+        })
+    }
+
+    /// Parses the optional `:format` suffix of a time directive. The format
+    /// runs until the closing brace and is checked to be a valid strftime
+    /// format.
+    fn parse_time_format(&mut self, default: &str) -> Result<String> {
+        if self.current_byte() != Some(b':') {
+            return Ok(default.to_owned());
+        }
+        self.advance();
+        let start_idx = self.current_idx;
+        loop {
+            match self.current_char() {
+                | Some('}') if start_idx != self.current_idx => break,
+                | Some('{' | '}') | None => return Err(self.unexpected(TIME_FORMATS)),
+                | Some(_) => self.advance(),
+            }
+        }
+        let span = start_idx..self.current_idx;
+        let format = &self.input[span.clone()];
+        if StrftimeItems::new(format).any(|item| item == Item::Error) {
+            return Err(self.error(
+                span,
+                TemplateErrorKind::InvalidFormatSpec(format!("invalid time format `{format}`")),
+                TIME_FORMATS,
+            ));
+        }
+        Ok(format.to_owned())
+    }
+
+    /// Parses the optional `|fallback` suffix of a directive. The fallback runs
+    /// until the closing brace and may not contain braces itself.
+    fn parse_fallback(&mut self) -> Result<String> {
+        if self.current_byte() != Some(b'|') {
+            return Ok(String::new());
+        }
+        self.advance();
+        let start_idx = self.current_idx;
+        loop {
+            match self.current_char() {
+                | Some('}') => break,
+                | Some('{') | None => return Err(self.unexpected(&["`}`", "fallback text"])),
+                | Some(_) => self.advance(),
+            }
+        }
+        Ok(self.input[start_idx..self.current_idx].to_owned())
+    }
+}
+
+/// What went wrong while parsing a template.
+#[non_exhaustive]
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub(crate) enum TemplateErrorKind {
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    /// Whitespace between an opening brace and a directive.
+    UnexpectedWhitespace,
+    UnknownDirective(String),
+    UnknownCondition(String),
+    InvalidFormatSpec(String),
+    MultiplePools,
+    NumberTooLarge,
+    DivisionByZero,
+}
+
+impl Display for TemplateErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            | Self::UnexpectedChar(c) => write!(f, "unexpected character `{c}`"),
+            | Self::UnexpectedEnd => write!(f, "unexpected end of template"),
+            | Self::UnexpectedWhitespace => write!(f, "unexpected whitespace before directive"),
+            | Self::UnknownDirective(name) => write!(f, "unknown directive `{name}`"),
+            | Self::UnknownCondition(name) => write!(f, "unknown condition `{name}`"),
+            | Self::InvalidFormatSpec(reason) => write!(f, "{reason}"),
+            | Self::MultiplePools => write!(f, "a template can only use one name pool"),
+            | Self::NumberTooLarge => write!(f, "number is too large"),
+            | Self::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+/// An error encountered while parsing a template, pointing at the exact bytes
+/// of the template that caused it.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub(crate) struct TemplateError {
+    /// The template that failed to parse.
+    pub(crate) source:   String,
+    /// The byte range of `source` that caused the error. Empty if the error
+    /// is at the end of the template.
+    pub(crate) span:     Range<usize>,
+    pub(crate) kind:     TemplateErrorKind,
+    /// The tokens that would have been accepted instead.
+    pub(crate) expected: &'static [&'static str],
+}
+
+impl TemplateError {
+    /// Returns the one based line and column, counted in characters, that the
+    /// error starts at.
+    pub(crate) fn line_col(&self) -> (usize, usize) {
+        let before = &self.source[..self.span.start];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+        let col = before[line_start..].chars().count() + 1;
+        (line, col)
+    }
+
+    fn expected_message(&self) -> Option<String> {
+        Some(match self.expected {
+            | [] => return None,
+            | [token] => format!("expected {token}"),
+            | [init @ .., last] => format!("expected one of {} or {last}", init.join(", ")),
+        })
+    }
+
+    /// Renders the offending line of the template with a caret under the bad
+    /// characters.
+    pub(crate) fn render_snippet(&self) -> String {
+        let (line, col) = self.line_col();
+        let line_start = self.source[..self.span.start]
+            .rfind('\n')
+            .map_or(0, |idx| idx + 1);
+        let line_end = self.source[self.span.start..]
+            .find('\n')
+            .map_or(self.source.len(), |idx| self.span.start + idx);
+        let line_text = &self.source[line_start..line_end];
+        let caret_len = self.source[self.span.start..self.span.end.min(line_end)]
+            .chars()
+            .count()
+            .max(1);
+        let gutter = " ".repeat(line.to_string().len());
+
+        let mut snippet = format!("error: {}\n", self.kind);
+        // Writing to a string can't fail.
+        writeln!(snippet, "{gutter}--> {line}:{col}").drop();
+        writeln!(snippet, "{gutter} |").drop();
+        writeln!(snippet, "{line} | {line_text}").drop();
+        write!(
+            snippet,
+            "{gutter} | {}{}",
+            " ".repeat(col - 1),
+            "^".repeat(caret_len)
+        )
+        .drop();
+        if let Some(expected) = self.expected_message() {
+            write!(snippet, " {expected}").drop();
+        }
+        snippet
+    }
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (line, col) = self.line_col();
+        write!(f, "{} at line {line}, column {col}", self.kind)?;
+        if let Some(expected) = self.expected_message() {
+            write!(f, ", {expected}")?;
+        }
+        Ok(())
     }
 }
 
+impl Error for TemplateError {}
+
 #[non_exhaustive]
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub(crate) enum TemplatePart {
-    ChannelNumber,
-    ChildrenInTotal,
+    ChannelNumber(NumberFormat),
+    ChildrenInTotal(NumberFormat),
+    /// The display name of the member whose join created the child.
+    OwnerName,
+    /// The nickname of the member whose join created the child, falling back to
+    /// their display name.
+    OwnerNick,
+    /// The game most members of the child are playing, or the fallback if
+    /// nobody is playing anything.
+    Game {
+        fallback: String,
+    },
+    /// The number of members connected to the child.
+    Users(NumberFormat),
+    /// The capacity of the child, or `∞` if it is unlimited.
+    Capacity(NumberFormat),
+    /// An arithmetic expression over the numeric directives, e.g. `{# + 100}`.
+    /// Renders `∞` if it involves an unlimited capacity.
+    Expression {
+        expr:   Expr,
+        format: NumberFormat,
+    },
+    /// The current time in the guild's time zone.
+    Time {
+        format: String,
+    },
+    /// The current date in the guild's time zone.
+    Date {
+        format: String,
+    },
+    /// The name of the current day of the week in the guild's time zone.
+    Weekday,
+    /// The value of a variable defined for the guild, or nothing if it isn't
+    /// defined.
+    Variable(String),
+    /// The entry of the named pool allocated to the child, or its number if the
+    /// pool was exhausted.
+    Pool(String),
+    /// One of the options, picked at random when the child is created.
+    Random {
+        /// The position of this pick among all picks in the template, which
+        /// its choice is stored under.
+        index:   usize,
+        options: Vec<String>,
+    },
+    /// Renders `then` if the condition holds, otherwise `otherwise`.
+    Conditional {
+        condition: Condition,
+        negated:   bool,
+        then:      Vec<TemplatePart>,
+        otherwise: Vec<TemplatePart>,
+    },
     String(String),
 }
 
+/// A condition on the live state of a child that a conditional section can
+/// test.
+#[non_exhaustive]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub(crate) enum Condition {
+    /// The child has a capacity and it has been reached.
+    Full,
+    /// Nobody is connected to the child.
+    Empty,
+    /// The child has a capacity.
+    Capped,
+    /// Someone in the child is playing a game.
+    Playing,
+}
+
+/// The format used by `{time}` when none is given.
+pub(crate) const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+/// The format used by `{date}` when none is given.
+pub(crate) const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            | Self::Full => write!(f, "full"),
+            | Self::Empty => write!(f, "empty"),
+            | Self::Capped => write!(f, "cap"),
+            | Self::Playing => write!(f, "game"),
+        }
+    }
+}
+
+/// Writes text, doubling braces and any of the terminators of the enclosing
+/// section so that they parse back as text.
+fn write_escaped(f: &mut Formatter<'_>, text: &str, terminators: &[u8]) -> fmt::Result {
+    for c in text.chars() {
+        if matches!(c, '{' | '}') || u8::try_from(c).is_ok_and(|b| terminators.contains(&b)) {
+            f.write_char(c)?;
+        }
+        f.write_char(c)?;
+    }
+    Ok(())
+}
+
+/// Writes parts that are terminated by one of `terminators`.
+fn write_parts(f: &mut Formatter<'_>, parts: &[TemplatePart], terminators: &[u8]) -> fmt::Result {
+    for part in parts {
+        match part {
+            | TemplatePart::String(s) => write_escaped(f, s, terminators)?,
+            | part => write!(f, "{part}")?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes the `:spec` suffix of a numeric directive, if it has one.
+fn write_number_format(f: &mut Formatter<'_>, format: NumberFormat) -> fmt::Result {
+    match format {
+        | NumberFormat::Plain => Ok(()),
+        | format => write!(f, ":{format}"),
+    }
+}
+
+/// Writes the part as canonical template source. Directives are written with
+/// the shortest syntax that parses back to the same part, e.g. `{time}` rather
+/// than `{time:%H:%M}`.
+impl Display for TemplatePart {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            | Self::String(s) => write_escaped(f, s, &[]),
+            | Self::ChannelNumber(format) => {
+                write!(f, "{{#")?;
+                write_number_format(f, *format)?;
+                write!(f, "}}")
+            },
+            | Self::ChildrenInTotal(format) => {
+                write!(f, "{{%")?;
+                write_number_format(f, *format)?;
+                write!(f, "}}")
+            },
+            | Self::Users(format) => {
+                write!(f, "{{users")?;
+                write_number_format(f, *format)?;
+                write!(f, "}}")
+            },
+            | Self::Capacity(format) => {
+                write!(f, "{{cap")?;
+                write_number_format(f, *format)?;
+                write!(f, "}}")
+            },
+            | Self::Expression { expr, format } => {
+                write!(f, "{{{expr}")?;
+                write_number_format(f, *format)?;
+                write!(f, "}}")
+            },
+            | Self::OwnerName => write!(f, "{{owner}}"),
+            | Self::OwnerNick => write!(f, "{{owner_nick}}"),
+            | Self::Game { fallback } if fallback.is_empty() => write!(f, "{{game}}"),
+            | Self::Game { fallback } => write!(f, "{{game|{fallback}}}"),
+            | Self::Time { format } if format == DEFAULT_TIME_FORMAT => write!(f, "{{time}}"),
+            | Self::Time { format } => write!(f, "{{time:{format}}}"),
+            | Self::Date { format } if format == DEFAULT_DATE_FORMAT => write!(f, "{{date}}"),
+            | Self::Date { format } => write!(f, "{{date:{format}}}"),
+            | Self::Weekday => write!(f, "{{weekday}}"),
+            | Self::Variable(name) => write!(f, "{{${name}}}"),
+            | Self::Pool(name) => write!(f, "{{pool:{name}}}"),
+            | Self::Random { options, .. } => write!(f, "{{rand:{}}}", options.join("|")),
+            | Self::Conditional {
+                condition,
+                negated,
+                then,
+                otherwise,
+            } => {
+                write!(f, "{{?{}{condition}:", if *negated { "!" } else { "" })?;
+                write_parts(f, then, b"|}")?;
+                if !otherwise.is_empty() {
+                    write!(f, "|")?;
+                    write_parts(f, otherwise, b"|}")?;
+                }
+                write!(f, "}}")
+            },
+        }
+    }
+}
+
+impl TemplatePart {
+    fn is_time_dependent(&self) -> bool {
+        match self {
+            | Self::Time { .. } | Self::Date { .. } | Self::Weekday => true,
+            | Self::Conditional {
+                then, otherwise, ..
+            } => then.iter().chain(otherwise).any(Self::is_time_dependent),
+            | _ => false,
+        }
+    }
+
+    fn is_locale_dependent(&self) -> bool {
+        match self {
+            | Self::Time { .. } | Self::Date { .. } | Self::Weekday => true,
+            | Self::ChannelNumber(format)
+            | Self::ChildrenInTotal(format)
+            | Self::Users(format)
+            | Self::Capacity(format)
+            | Self::Expression { format, .. } => format.is_locale_dependent(),
+            | Self::Conditional {
+                then, otherwise, ..
+            } => then.iter().chain(otherwise).any(Self::is_locale_dependent),
+            | _ => false,
+        }
+    }
+
+    fn uses_variable(&self, name: &str) -> bool {
+        match self {
+            | Self::Variable(variable) => variable == name,
+            | Self::Conditional {
+                then, otherwise, ..
+            } => then
+                .iter()
+                .chain(otherwise)
+                .any(|part| part.uses_variable(name)),
+            | _ => false,
+        }
+    }
+
+    fn uses_variables(&self) -> bool {
+        match self {
+            | Self::Variable(_) => true,
+            | Self::Conditional {
+                then, otherwise, ..
+            } => then.iter().chain(otherwise).any(Self::uses_variables),
+            | _ => false,
+        }
+    }
+}
+
 #[non_exhaustive]
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Default)]
 pub(crate) struct Template {
     pub(crate) parts: Vec<TemplatePart>,
 }
 
+impl Template {
+    /// Returns true if the rendered name changes over time, so that it has to
+    /// be re-rendered periodically rather than only on voice events.
+    pub(crate) fn is_time_dependent(&self) -> bool {
+        self.parts.iter().any(TemplatePart::is_time_dependent)
+    }
+
+    /// Returns true if the template spells out numbers or names days or months,
+    /// which depends on the guild's locale.
+    pub(crate) fn is_locale_dependent(&self) -> bool {
+        self.parts.iter().any(TemplatePart::is_locale_dependent)
+    }
+
+    /// Returns true if the template references the given guild variable.
+    pub(crate) fn uses_variable(&self, name: &str) -> bool {
+        self.parts.iter().any(|part| part.uses_variable(name))
+    }
+
+    /// Returns the name pool the template allocates entries from, if any.
+    pub(crate) fn pool_name(&self) -> Option<&str> {
+        fn find(parts: &[TemplatePart]) -> Option<&str> {
+            parts.iter().find_map(|part| match part {
+                | TemplatePart::Pool(name) => Some(name.as_str()),
+                | TemplatePart::Conditional {
+                    then, otherwise, ..
+                } => find(then).or_else(|| find(otherwise)),
+                | _ => None,
+            })
+        }
+        find(&self.parts)
+    }
+
+    /// Returns the number of options of every random pick in the template,
+    /// ordered by their index.
+    pub(crate) fn random_picks(&self) -> Vec<usize> {
+        fn collect(parts: &[TemplatePart], picks: &mut Vec<usize>) {
+            for part in parts {
+                match part {
+                    | TemplatePart::Random { options, .. } => picks.push(options.len()),
+                    | TemplatePart::Conditional {
+                        then, otherwise, ..
+                    } => {
+                        collect(then, picks);
+                        collect(otherwise, picks);
+                    },
+                    | _ => {},
+                }
+            }
+        }
+        let mut picks = vec![];
+        collect(&self.parts, &mut picks);
+        picks
+    }
+
+    /// Returns true if the template references any guild variable.
+    pub(crate) fn uses_variables(&self) -> bool {
+        self.parts.iter().any(TemplatePart::uses_variables)
+    }
+}
+
+/// Writes the template as canonical source, which parses back to an equal
+/// template.
+impl Display for Template {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_parts(f, &self.parts, &[])
+    }
+}
+
 pub(crate) fn parse_template(template: &str) -> Result<Template> {
     Parser::new(template).parse()
 }
 
+/// Parses a bare arithmetic expression without braces or a format specifier,
+/// such as the `# * 8` of a bitrate template.
+pub(crate) fn parse_expression(expression: &str) -> Result<Expr> {
+    let mut parser = Parser::new(expression);
+    let expr = parser.parse_sum()?;
+    if parser.peek_token().1 != Token::End || parser.current_idx != expression.len() {
+        return Err(parser.unexpected(BINARY_OPERATORS));
+    }
+    Ok(expr)
+}
+
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
     use rstest::rstest;
 
     use super::*;
@@ -194,15 +1060,436 @@ mod tests {
         parts: vec![TemplatePart::String("Röstkanal".into())]
     })]
     #[case("Röstkanal {#}#", Template {
-        parts: vec![TemplatePart::String("Röstkanal ".into()), TemplatePart::ChannelNumber, TemplatePart::String("#".into())]
+        parts: vec![TemplatePart::String("Röstkanal ".into()), TemplatePart::ChannelNumber(NumberFormat::Plain), TemplatePart::String("#".into())]
     })]
     #[case("Röstkanal {{#}}#", Template {
         parts: vec![TemplatePart::String("Röstkanal {#}#".into())]
     })]
     #[case("Röstkanal {{{#}}}#", Template {
-        parts: vec![TemplatePart::String("Röstkanal {".into()), TemplatePart::ChannelNumber, TemplatePart::String("}#".into())]
+        parts: vec![TemplatePart::String("Röstkanal {".into()), TemplatePart::ChannelNumber(NumberFormat::Plain), TemplatePart::String("}#".into())]
+    })]
+    #[case("{owner}'s channel", Template {
+        parts: vec![TemplatePart::OwnerName, TemplatePart::String("'s channel".into())]
+    })]
+    #[case("{owner_nick} #{#}", Template {
+        parts: vec![TemplatePart::OwnerNick, TemplatePart::String(" #".into()), TemplatePart::ChannelNumber(NumberFormat::Plain)]
+    })]
+    #[case("{game} #{#}", Template {
+        parts: vec![TemplatePart::Game { fallback: String::new() }, TemplatePart::String(" #".into()), TemplatePart::ChannelNumber(NumberFormat::Plain)]
+    })]
+    #[case("{game|Just chatting}", Template {
+        parts: vec![TemplatePart::Game { fallback: "Just chatting".into() }]
+    })]
+    #[case("Squad {#:roman} of {%:words}", Template {
+        parts: vec![TemplatePart::String("Squad ".into()), TemplatePart::ChannelNumber(NumberFormat::Roman), TemplatePart::String(" of ".into()), TemplatePart::ChildrenInTotal(NumberFormat::Words)]
+    })]
+    #[case("Room {#:02}", Template {
+        parts: vec![TemplatePart::String("Room ".into()), TemplatePart::ChannelNumber(NumberFormat::ZeroPadded(2))]
+    })]
+    #[case("{?full:🔴|🟢} Lobby", Template {
+        parts: vec![
+            TemplatePart::Conditional {
+                condition: Condition::Full,
+                negated: false,
+                then: vec![TemplatePart::String("🔴".into())],
+                otherwise: vec![TemplatePart::String("🟢".into())],
+            },
+            TemplatePart::String(" Lobby".into()),
+        ]
+    })]
+    #[case("{?!empty:{#:roman} {{||}}}", Template {
+        parts: vec![TemplatePart::Conditional {
+            condition: Condition::Empty,
+            negated: true,
+            then: vec![TemplatePart::ChannelNumber(NumberFormat::Roman), TemplatePart::String(" {|}".into())],
+            otherwise: vec![],
+        }]
+    })]
+    #[case("{{#}} | {%}", Template {
+        parts: vec![TemplatePart::String("{#} | ".into()), TemplatePart::ChildrenInTotal(NumberFormat::Plain)]
+    })]
+    #[case("{?cap: ({users}/{cap:02})}", Template {
+        parts: vec![TemplatePart::Conditional {
+            condition: Condition::Capped,
+            negated: false,
+            then: vec![
+                TemplatePart::String(" (".into()),
+                TemplatePart::Users(NumberFormat::Plain),
+                TemplatePart::String("/".into()),
+                TemplatePart::Capacity(NumberFormat::ZeroPadded(2)),
+                TemplatePart::String(")".into()),
+            ],
+            otherwise: vec![],
+        }]
+    })]
+    #[case("{weekday} Night Lobby {#}", Template {
+        parts: vec![TemplatePart::Weekday, TemplatePart::String(" Night Lobby ".into()), TemplatePart::ChannelNumber(NumberFormat::Plain)]
+    })]
+    #[case("{time} {date}", Template {
+        parts: vec![TemplatePart::Time { format: "%H:%M".into() }, TemplatePart::String(" ".into()), TemplatePart::Date { format: "%Y-%m-%d".into() }]
+    })]
+    #[case("{time:%I:%M %p}", Template {
+        parts: vec![TemplatePart::Time { format: "%I:%M %p".into() }]
+    })]
+    #[case("Season {$season} #{#}", Template {
+        parts: vec![TemplatePart::String("Season ".into()), TemplatePart::Variable("season".into()), TemplatePart::String(" #".into()), TemplatePart::ChannelNumber(NumberFormat::Plain)]
+    })]
+    #[case("{pool:nato} {?full:({pool:nato})}", Template {
+        parts: vec![TemplatePart::Pool("nato".into()), TemplatePart::String(" ".into()), TemplatePart::Conditional {
+            condition: Condition::Full,
+            negated: false,
+            then: vec![TemplatePart::String("(".into()), TemplatePart::Pool("nato".into()), TemplatePart::String(")".into())],
+            otherwise: vec![],
+        }]
+    })]
+    #[case("{rand:Cozy|Chill|Comfy} {?full:{rand:!}|{rand:|?}}", Template {
+        parts: vec![
+            TemplatePart::Random { index: 0, options: vec!["Cozy".into(), "Chill".into(), "Comfy".into()] },
+            TemplatePart::String(" ".into()),
+            TemplatePart::Conditional {
+                condition: Condition::Full,
+                negated: false,
+                then: vec![TemplatePart::Random { index: 1, options: vec!["!".into()] }],
+                otherwise: vec![TemplatePart::Random { index: 2, options: vec![String::new(), "?".into()] }],
+            },
+        ]
+    })]
+    #[case("Room {#+100}", Template {
+        parts: vec![TemplatePart::String("Room ".into()), TemplatePart::Expression {
+            expr: Expr::binary(BinaryOp::Add, Expr::Operand(Operand::ChannelNumber), Expr::Operand(Operand::Literal(100))),
+            format: NumberFormat::Plain,
+        }]
+    })]
+    #[case("{ # * 2 - % :02}", Template {
+        parts: vec![TemplatePart::Expression {
+            expr: Expr::binary(
+                BinaryOp::Sub,
+                Expr::binary(BinaryOp::Mul, Expr::Operand(Operand::ChannelNumber), Expr::Operand(Operand::Literal(2))),
+                Expr::Operand(Operand::ChildrenInTotal),
+            ),
+            format: NumberFormat::ZeroPadded(2),
+        }]
+    })]
+    #[case("{-(cap-users)}", Template {
+        parts: vec![TemplatePart::Expression {
+            expr: Expr::Negate(Box::new(Expr::binary(BinaryOp::Sub, Expr::Operand(Operand::Capacity), Expr::Operand(Operand::Users)))),
+            format: NumberFormat::Plain,
+        }]
+    })]
+    #[case("{(users)}", Template {
+        parts: vec![TemplatePart::Users(NumberFormat::Plain)]
     })]
     fn test_parses(#[case] input: &str, #[case] expected: Template) {
         assert_eq!(expected, parse_template(input).unwrap(),);
     }
+
+    #[rstest]
+    #[case("{owner")]
+    #[case("{ownr}")]
+    #[case("{owner nick}")]
+    #[case("{game|a{b}")]
+    #[case("{#:}")]
+    #[case("{users:hex}")]
+    #[case("{?full}")]
+    #[case("{?ful:a}")]
+    #[case("{?full:a|b|c}")]
+    #[case("{?full:a")]
+    #[case("{#:hex}")]
+    #[case("{%:02")]
+    #[case("{game|unterminated")]
+    #[case("{time:}")]
+    #[case("{time:%Q}")]
+    #[case("{date:%d")]
+    #[case("{$}")]
+    #[case("{$sea son}")]
+    #[case("{pool}")]
+    #[case("{pool:}")]
+    #[case("{rand}")]
+    #[case("{rand:a|{#}}")]
+    #[case("{rand:a|b")]
+    #[case("{}")]
+    #[case("{#+}")]
+    #[case("{# #}")]
+    #[case("{(#}")]
+    #[case("{#/0}")]
+    #[case("{#+owner}")]
+    fn test_rejects(#[case] input: &str) {
+        assert!(parse_template(input).is_err());
+    }
+
+    #[rstest]
+    #[case("{ownr}", 1..5, (1, 2))]
+    #[case("Röstkanal {#:hex}", 14..17, (1, 14))]
+    #[case("a\nbc {?full:x", 13..13, (2, 12))]
+    #[case("{#", 2..2, (1, 3))]
+    #[case("{date:%d %Q}", 6..11, (1, 7))]
+    #[case("{pool:nato} {pool:greek}", 18..23, (1, 19))]
+    #[case("{# / 0}", 5..6, (1, 6))]
+    #[case("{99999999999999999999}", 1..21, (1, 2))]
+    #[case("{# & 2}", 3..4, (1, 4))]
+    #[case("{ owner}", 1..2, (1, 2))]
+    fn test_error_position(
+        #[case] input: &str,
+        #[case] span: Range<usize>,
+        #[case] line_col: (usize, usize),
+    ) {
+        let err = parse_template(input).unwrap_err();
+        assert_eq!(span, err.span);
+        assert_eq!(line_col, err.line_col());
+    }
+
+    #[rstest]
+    #[case("{ owner}", 1..2)]
+    #[case("{ \t?full:a}", 1..3)]
+    #[case("{  $season}", 1..3)]
+    #[case("a {?full:{ game}}", 10..11)]
+    fn test_rejects_whitespace_before_directive(#[case] input: &str, #[case] span: Range<usize>) {
+        let err = parse_template(input).unwrap_err();
+        assert_eq!(TemplateErrorKind::UnexpectedWhitespace, err.kind);
+        assert_eq!(span, err.span);
+    }
+
+    #[rstest]
+    #[case("64", Expr::Operand(Operand::Literal(64)))]
+    #[case(
+        " users * 2 ",
+        Expr::binary(
+            BinaryOp::Mul,
+            Expr::Operand(Operand::Users),
+            Expr::Operand(Operand::Literal(2))
+        )
+    )]
+    fn test_parse_expression(#[case] input: &str, #[case] expected: Expr) {
+        assert_eq!(expected, parse_expression(input).unwrap());
+    }
+
+    #[rstest]
+    #[case("", 0..0)]
+    #[case("users:02", 5..6)]
+    #[case("{#}", 0..1)]
+    #[case("# 2", 2..3)]
+    fn test_parse_expression_rejects(#[case] input: &str, #[case] span: Range<usize>) {
+        assert_eq!(span, parse_expression(input).unwrap_err().span);
+    }
+
+    #[rstest]
+    #[case("Lobby {#}", false)]
+    #[case("{weekday} Lobby", true)]
+    #[case("{?full:Full {time}|Open}", true)]
+    fn test_is_time_dependent(#[case] input: &str, #[case] expected: bool) {
+        assert_eq!(expected, parse_template(input).unwrap().is_time_dependent());
+    }
+
+    #[rstest]
+    #[case("Lobby {#}", false)]
+    #[case("{$season} Lobby", true)]
+    #[case("{$seasons} Lobby", false)]
+    #[case("{?!empty:{$season}}", true)]
+    fn test_uses_variable(#[case] input: &str, #[case] expected: bool) {
+        assert_eq!(
+            expected,
+            parse_template(input).unwrap().uses_variable("season")
+        );
+    }
+
+    #[rstest]
+    #[case("Lobby {#}", None)]
+    #[case("{pool:nato} {pool:nato}", Some("nato"))]
+    #[case("{?!empty:{pool:greek}}", Some("greek"))]
+    fn test_pool_name(#[case] input: &str, #[case] expected: Option<&str>) {
+        assert_eq!(expected, parse_template(input).unwrap().pool_name());
+    }
+
+    #[test]
+    fn test_render_snippet() {
+        let err = parse_template("Röstkanal {ownr}").unwrap_err();
+        assert_eq!(
+            "error: unknown directive `ownr`\n --> 1:12\n  |\n1 | Röstkanal {ownr}\n  |            \
+             ^^^^ expected one of `owner`, `owner_nick`, `game`, `users`, `cap`, `time`, `date`, \
+             `weekday`, `pool` or `rand`",
+            err.render_snippet()
+        );
+    }
+
+    #[rstest]
+    #[case("Röstkanal {{#}} {#}", "Röstkanal {{#}} {#}")]
+    #[case("{ # * 2 - % :02}", "{# * 2 - %:02}")]
+    #[case("{(#+1)*2}", "{(# + 1) * 2}")]
+    #[case("{time:%H:%M} {date}", "{time} {date}")]
+    #[case("{game|}", "{game}")]
+    #[case("{#:letter}", "{#:letters}")]
+    #[case("{?!full:a}}||b|}", "{?!full:a}}||b}")]
+    #[case("{?game:{game|x}}", "{?game:{game|x}}")]
+    #[case(
+        "{$season} {pool:nato} {rand:a|b:c}",
+        "{$season} {pool:nato} {rand:a|b:c}"
+    )]
+    fn test_display(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, parse_template(input).unwrap().to_string());
+    }
+
+    fn expr_strategy() -> impl Strategy<Value = Expr> {
+        let leaf = prop_oneof![
+            Just(Operand::ChannelNumber),
+            Just(Operand::ChildrenInTotal),
+            Just(Operand::Users),
+            Just(Operand::Capacity),
+            // A literal zero divisor is rejected by the parser.
+            (1..1000_i64).prop_map(Operand::Literal),
+        ]
+        .prop_map(Expr::Operand);
+        leaf.prop_recursive(4, 16, 2, |inner| {
+            prop_oneof![
+                inner.clone().prop_map(|expr| Expr::Negate(Box::new(expr))),
+                (
+                    prop_oneof![
+                        Just(BinaryOp::Add),
+                        Just(BinaryOp::Sub),
+                        Just(BinaryOp::Mul),
+                        Just(BinaryOp::Div),
+                    ],
+                    inner.clone(),
+                    inner,
+                )
+                    .prop_map(|(op, lhs, rhs)| Expr::binary(op, lhs, rhs)),
+            ]
+        })
+    }
+
+    fn number_format_strategy() -> impl Strategy<Value = NumberFormat> {
+        prop_oneof![
+            Just(NumberFormat::Plain),
+            (0..=20_usize).prop_map(NumberFormat::ZeroPadded),
+            Just(NumberFormat::Roman),
+            Just(NumberFormat::Words),
+            Just(NumberFormat::Ordinal),
+            Just(NumberFormat::Emoji),
+            Just(NumberFormat::Letters),
+        ]
+    }
+
+    fn part_strategy() -> impl Strategy<Value = TemplatePart> {
+        let leaf = prop_oneof![
+            "[a-z #:|{}]{1,6}".prop_map(TemplatePart::String),
+            number_format_strategy().prop_map(TemplatePart::ChannelNumber),
+            number_format_strategy().prop_map(TemplatePart::ChildrenInTotal),
+            number_format_strategy().prop_map(TemplatePart::Users),
+            number_format_strategy().prop_map(TemplatePart::Capacity),
+            (expr_strategy(), number_format_strategy())
+                // Lone operands are parsed as their own parts.
+                .prop_filter("lone operand", |(expr, _)| !matches!(
+                    expr,
+                    Expr::Operand(operand) if !matches!(operand, Operand::Literal(_))
+                ))
+                .prop_map(|(expr, format)| TemplatePart::Expression { expr, format }),
+            Just(TemplatePart::OwnerName),
+            Just(TemplatePart::OwnerNick),
+            "[a-z :|]{0,6}".prop_map(|fallback| TemplatePart::Game { fallback }),
+            prop_oneof![Just("%H:%M"), Just("%I %p")].prop_map(|format| TemplatePart::Time {
+                format: format.to_owned(),
+            }),
+            prop_oneof![Just("%Y-%m-%d"), Just("%d.%m")].prop_map(|format| TemplatePart::Date {
+                format: format.to_owned(),
+            }),
+            Just(TemplatePart::Weekday),
+            "[a-z_][a-z0-9_]{0,5}".prop_map(TemplatePart::Variable),
+            // A template may only use one pool.
+            Just(TemplatePart::Pool("nato".into())),
+            prop::collection::vec("[a-z :#]{0,4}", 1..4)
+                .prop_map(|options| TemplatePart::Random { index: 0, options }),
+        ];
+        leaf.prop_recursive(3, 24, 4, |inner| {
+            (
+                prop_oneof![
+                    Just(Condition::Full),
+                    Just(Condition::Empty),
+                    Just(Condition::Capped),
+                    Just(Condition::Playing),
+                ],
+                any::<bool>(),
+                prop::collection::vec(inner.clone(), 0..4),
+                prop::collection::vec(inner, 0..4),
+            )
+                .prop_map(|(condition, negated, then, otherwise)| {
+                    TemplatePart::Conditional {
+                        condition,
+                        negated,
+                        then: normalize(then),
+                        otherwise: normalize(otherwise),
+                    }
+                })
+        })
+    }
+
+    /// Merges adjacent strings, which the parser never produces.
+    fn normalize(parts: Vec<TemplatePart>) -> Vec<TemplatePart> {
+        let mut normalized: Vec<TemplatePart> = vec![];
+        for part in parts {
+            match (normalized.last_mut(), part) {
+                | (Some(TemplatePart::String(last)), TemplatePart::String(s)) => last.push_str(&s),
+                | (_, part) => normalized.push(part),
+            }
+        }
+        normalized
+    }
+
+    /// Numbers random picks in the order the parser encounters them.
+    fn number_random_picks(parts: &mut [TemplatePart], next: &mut usize) {
+        for part in parts {
+            match part {
+                | TemplatePart::Random { index, .. } => {
+                    *index = *next;
+                    *next += 1;
+                },
+                | TemplatePart::Conditional {
+                    then, otherwise, ..
+                } => {
+                    number_random_picks(then, next);
+                    number_random_picks(otherwise, next);
+                },
+                | _ => {},
+            }
+        }
+    }
+
+    /// Returns false if a conditional is directly followed by `}` or its
+    /// otherwise branch starts with `|`, neither of which can be written since
+    /// doubled braces and pipes inside a branch are escapes.
+    fn is_expressible(parts: &[TemplatePart], followed_by_brace: bool) -> bool {
+        parts.iter().enumerate().all(|(idx, part)| match part {
+            | TemplatePart::Conditional {
+                then, otherwise, ..
+            } => {
+                let next_is_brace = match parts.get(idx + 1) {
+                    | Some(TemplatePart::String(s)) => s.starts_with('}'),
+                    | Some(_) => false,
+                    | None => followed_by_brace,
+                };
+                let otherwise_starts_with_pipe = matches!(
+                    otherwise.first(),
+                    Some(TemplatePart::String(s)) if s.starts_with('|')
+                );
+                !next_is_brace
+                    && !otherwise_starts_with_pipe
+                    && is_expressible(then, otherwise.is_empty())
+                    && is_expressible(otherwise, true)
+            },
+            | _ => true,
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_display_round_trips(
+            parts in prop::collection::vec(part_strategy(), 0..6)
+                .prop_map(normalize)
+                .prop_filter("inexpressible", |parts| is_expressible(parts, false))
+        ) {
+            let mut parts = parts;
+            number_random_picks(&mut parts, &mut 0);
+            let template = Template { parts };
+            let source = template.to_string();
+            prop_assert_eq!(Ok(template), parse_template(&source), "source: {}", source);
+        }
+    }
 }
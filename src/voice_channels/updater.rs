@@ -1,78 +1,738 @@
-use std::fmt::{
-    self,
-    Write,
+use chrono::{
+    DateTime,
+    Utc,
 };
-
+use chrono_tz::Tz;
 use eyre::{
     eyre,
     Result,
     WrapErr,
 };
+use rand::Rng;
+use serde::{
+    Deserialize,
+    Serialize,
+};
 use serenity::{
     builder::EditChannel,
     client::Context as SerenityContext,
-    model::channel::GuildChannel,
+    model::{
+        channel::GuildChannel,
+        guild::{
+            Member,
+            PremiumTier,
+        },
+        id::ChannelId,
+    },
 };
 use tracing::{
     debug,
     info,
 };
 
-use super::parser::{
-    Template,
-    TemplatePart,
+use super::{
+    expr::{
+        EvalError,
+        Expr,
+        Operand,
+    },
+    parser::{
+        parse_expression,
+        parse_template,
+        Condition,
+        Template,
+        TemplateError,
+        TemplatePart,
+    },
+};
+use crate::{
+    locale::Locale,
+    DropExt,
+    HashMap,
 };
-pub(crate) struct SerenityContextWrapper<'ctx>(pub(crate) &'ctx SerenityContext);
 
-impl fmt::Debug for SerenityContextWrapper<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("SerenityContextWrapper")
-            .field("data", &"Arc(RwLock(TypeMap{...}))")
-            .field("http", &"Arc(Http)")
-            .field("cache", &"Arc(Cache)")
-            .field("shard", &"Arc(ShardMessenger)")
-            .field("shard_id", &self.0.shard_id)
-            .finish()
+/// The maximum length of a channel name allowed by Discord, counted in
+/// characters.
+pub(crate) const MAX_CHANNEL_NAME_LENGTH: usize = 100;
+
+/// The maximum length of a voice channel status allowed by Discord, counted in
+/// characters.
+const MAX_STATUS_LENGTH: usize = 500;
+
+/// The lowest bitrate of a voice channel allowed by Discord, in kbps.
+const MIN_BITRATE: i64 = 8;
+
+/// The highest user limit of a voice channel allowed by Discord.
+const MAX_USER_LIMIT: i64 = 99;
+
+/// The maximum length of a member or game name inserted into a channel name.
+/// Matches Discord's own limit for nicknames.
+const MAX_INSERTED_NAME_LENGTH: usize = 32;
+
+/// The member whose join created a channel.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OwnerInfo {
+    /// The global name of the member, or their username if they have none.
+    pub(crate) display_name: String,
+    pub(crate) nick:         Option<String>,
+}
+
+impl From<&Member> for OwnerInfo {
+    fn from(member: &Member) -> Self {
+        // `Member::display_name` prefers the nickname, which `{owner}` must not.
+        let display_name = member
+            .user
+            .global_name
+            .as_ref()
+            .unwrap_or(&member.user.name);
+        Self {
+            display_name: display_name.clone(),
+            nick:         member.nick.clone(),
+        }
+    }
+}
+
+/// State persisted per child so that re-rendering it gives a stable name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct RenderState {
+    /// The option chosen by each random pick of the template, by index.
+    #[serde(default)]
+    pub(crate) rand_choices: Vec<usize>,
+}
+
+impl RenderState {
+    /// Creates the state of a new child, choosing options at random.
+    pub(crate) fn new(template: &Template) -> Self {
+        let mut state = Self::default();
+        state.reconcile(template).drop();
+        state
+    }
+
+    /// Makes the state fit a possibly altered template, keeping choices that
+    /// are still valid and choosing the rest at random. Returns true if the
+    /// state changed and needs to be stored again.
+    pub(crate) fn reconcile(&mut self, template: &Template) -> bool {
+        let picks = template.random_picks();
+        let mut rng = rand::thread_rng();
+        let mut changed = self.rand_choices.len() != picks.len();
+        self.rand_choices.resize(picks.len(), usize::MAX);
+        for (choice, options) in self.rand_choices.iter_mut().zip(picks) {
+            if *choice >= options {
+                *choice = rng.gen_range(0..options.max(1));
+                changed = true;
+            }
+        }
+        changed
     }
 }
 
-#[derive(Debug)]
-pub(crate) struct UpdaterContext<'template, 'channel, 'ctx> {
-    pub(crate) template:              &'template Template,
-    pub(crate) channel:               &'channel mut GuildChannel,
-    pub(crate) context:               SerenityContextWrapper<'ctx>,
+/// Properties the owner of a child has set by hand, which take precedence over
+/// the templates of its parent.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ChannelOverrides {
+    pub(crate) name:       Option<String>,
+    /// The user limit, where zero means unlimited.
+    pub(crate) user_limit: Option<u32>,
+    /// The bitrate in kbps.
+    pub(crate) bitrate:    Option<u32>,
+}
+
+/// The values a template is rendered with.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RenderContext {
     pub(crate) channel_number:        u64,
     pub(crate) total_children_number: u64,
+    /// The member whose join created the channel, if they are still known.
+    pub(crate) owner:                 Option<OwnerInfo>,
+    /// The game most members of the channel are playing.
+    pub(crate) game:                  Option<String>,
+    /// The number of members connected to the channel.
+    pub(crate) users:                 u64,
+    /// The capacity of the channel, if it has one.
+    pub(crate) capacity:              Option<u64>,
+    /// The moment the name is rendered at.
+    pub(crate) now:                   DateTime<Utc>,
+    /// The time zone of the guild, which time directives are rendered in.
+    pub(crate) time_zone:             Tz,
+    /// The locale of the guild, which numbers are spelled out in.
+    pub(crate) locale:                Locale,
+    /// The variables defined for the guild, by name.
+    pub(crate) variables:             HashMap<String, String>,
+    /// The entry of the template's name pool allocated to the channel.
+    pub(crate) pool_entry:            Option<String>,
+    /// The state persisted for the channel.
+    pub(crate) state:                 RenderState,
+}
+
+/// The templates a parent renders the properties of its children with. Parsed
+/// once when the parent is loaded and shared with its children.
+#[derive(Debug, Default)]
+pub(crate) struct ChannelTemplates {
+    pub(crate) name:       Template,
+    pub(crate) status:     Option<Template>,
+    /// The bitrate in kbps.
+    pub(crate) bitrate:    Option<Expr>,
+    pub(crate) user_limit: Option<Expr>,
+}
+
+impl ChannelTemplates {
+    pub(crate) fn parse(
+        name: &str,
+        status: Option<&str>,
+        bitrate: Option<&str>,
+        user_limit: Option<&str>,
+    ) -> Result<Self, TemplateError> {
+        Ok(Self {
+            name:       parse_template(name)?,
+            status:     status.map(parse_template).transpose()?,
+            bitrate:    bitrate.map(parse_expression).transpose()?,
+            user_limit: user_limit.map(parse_expression).transpose()?,
+        })
+    }
+
+    /// Returns true if the predicate holds for the name or the status template.
+    pub(crate) fn any(&self, predicate: impl Fn(&Template) -> bool) -> bool {
+        predicate(&self.name) || self.status.as_ref().is_some_and(predicate)
+    }
+}
+
+/// The properties of a channel rendered from the templates of its parent.
+/// Properties without a template are `None` and left untouched.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ChannelUpdate {
+    pub(crate) name:       String,
+    pub(crate) status:     Option<String>,
+    /// The bitrate in bits per second.
+    pub(crate) bitrate:    Option<u32>,
+    /// The user limit, where zero means unlimited.
+    pub(crate) user_limit: Option<u32>,
+}
+
+/// The highest bitrate in kbps a guild with the given boost tier allows.
+pub(crate) fn max_bitrate(tier: PremiumTier) -> u32 {
+    #[cfg_attr(feature = "nightly-features", allow(non_exhaustive_omitted_patterns))]
+    match tier {
+        | PremiumTier::Tier1 => 128,
+        | PremiumTier::Tier2 => 256,
+        | PremiumTier::Tier3 => 384,
+        | _ => 96,
+    }
+}
+
+/// Renders every property of a channel that its parent has a template for or
+/// its owner has overridden. The bitrate is clamped between 8 kbps and
+/// `max_bitrate` and the user limit between 0 and 99. A user limit overrides
+/// the capacity `ctx` was created with, so that `{cap}` shows the limit the
+/// channel actually has.
+#[allow(clippy::cast_possible_truncation, reason = "Both values are clamped.")]
+pub(crate) fn render_channel(
+    templates: &ChannelTemplates,
+    overrides: &ChannelOverrides,
+    mut ctx: RenderContext,
+    max_bitrate: u32,
+) -> Result<ChannelUpdate, EvalError> {
+    let user_limit = match overrides.user_limit {
+        | Some(user_limit) => Some(user_limit.min(MAX_USER_LIMIT as u32)),
+        | None => templates
+            .user_limit
+            .as_ref()
+            .map(|expr| {
+                expr.evaluate(&|operand| ctx.value_of(operand))
+                    .map(|limit| limit.map_or(0, |limit| limit.clamp(0, MAX_USER_LIMIT)) as u32)
+            })
+            .transpose()?,
+    };
+    if let Some(user_limit) = user_limit {
+        ctx.capacity = Some(u64::from(user_limit));
+    }
+    let max_bitrate = i64::from(max_bitrate);
+    let bitrate = match overrides.bitrate {
+        | Some(bitrate) => Some(i64::from(bitrate).clamp(MIN_BITRATE, max_bitrate) as u32 * 1000),
+        | None => templates
+            .bitrate
+            .as_ref()
+            .map(|expr| {
+                expr.evaluate(&|operand| ctx.value_of(operand))
+                    .map(|bitrate| {
+                        bitrate.map_or(max_bitrate, |b| b.clamp(MIN_BITRATE, max_bitrate))
+                    })
+                    .map(|bitrate| bitrate as u32 * 1000)
+            })
+            .transpose()?,
+    };
+    let status = templates
+        .status
+        .as_ref()
+        .map(|status| render(status, &ctx))
+        .transpose()?
+        .map(|status| status.chars().take(MAX_STATUS_LENGTH).collect());
+    let name = match &overrides.name {
+        | Some(name) => name.clone(),
+        | None => render(&templates.name, &ctx)?,
+    };
+    Ok(ChannelUpdate {
+        name,
+        status,
+        bitrate,
+        user_limit,
+    })
 }
 
-pub(crate) async fn update_channel(ctx: UpdaterContext<'_, '_, '_>) -> Result<()> {
-    debug!("UpdaterContext: {ctx:#?}");
+/// Renders the name of a channel from a template. The name may be longer than
+/// Discord allows or empty. Fails if an expression in the template overflows or
+/// divides by zero.
+pub(crate) fn render(template: &Template, ctx: &RenderContext) -> Result<String, EvalError> {
     let mut new_name = String::new();
+    ctx.render_parts(&template.parts, &mut new_name)?;
+    Ok(new_name)
+}
+
+impl RenderContext {
+    /// The capacity of the channel, if it is limited. A capacity of zero means
+    /// the channel is unlimited.
+    fn limited_capacity(&self) -> Option<u64> {
+        self.capacity.filter(|&cap| cap > 0)
+    }
+
+    fn holds(&self, condition: Condition) -> bool {
+        let capacity = self.limited_capacity();
+        match condition {
+            | Condition::Full => capacity.is_some_and(|cap| self.users >= cap),
+            | Condition::Empty => self.users == 0,
+            | Condition::Capped => capacity.is_some(),
+            | Condition::Playing => self.game.is_some(),
+        }
+    }
 
-    for part in &ctx.template.parts {
-        debug!("part: {:?}", part,);
-        match part {
-            | TemplatePart::String(s) => new_name.push_str(s),
-            | TemplatePart::ChannelNumber => write!(new_name, "{}", ctx.channel_number)
-                .map_err(|e| eyre!(e))
-                .wrap_err_with(|| eyre!("Writing channel number into string failed!"))?,
-            | TemplatePart::ChildrenInTotal => write!(new_name, "{}", ctx.total_children_number)
-                .map_err(|e| eyre!(e))
-                .wrap_err_with(|| eyre!("Writing total child count into string failed!"))?,
+    fn value_of(&self, operand: Operand) -> Option<i64> {
+        match operand {
+            | Operand::ChannelNumber => Some(self.channel_number as i64),
+            | Operand::ChildrenInTotal => Some(self.total_children_number as i64),
+            | Operand::Users => Some(self.users as i64),
+            | Operand::Capacity => self.limited_capacity().map(|cap| cap as i64),
+            | Operand::Literal(value) => Some(value),
         }
     }
 
+    fn render_parts(&self, parts: &[TemplatePart], new_name: &mut String) -> Result<(), EvalError> {
+        for part in parts {
+            debug!("part: {:?}", part,);
+            match part {
+                | TemplatePart::String(s) => new_name.push_str(s),
+                | TemplatePart::ChannelNumber(format) =>
+                    new_name.push_str(&format.format(self.channel_number, self.locale)),
+                | TemplatePart::ChildrenInTotal(format) =>
+                    new_name.push_str(&format.format(self.total_children_number, self.locale)),
+                | TemplatePart::OwnerName =>
+                    if let Some(owner) = &self.owner {
+                        new_name.push_str(&sanitize_inserted_name(&owner.display_name));
+                    },
+                | TemplatePart::OwnerNick =>
+                    if let Some(owner) = &self.owner {
+                        let nick = owner.nick.as_ref().unwrap_or(&owner.display_name);
+                        new_name.push_str(&sanitize_inserted_name(nick));
+                    },
+                | TemplatePart::Game { fallback } => match &self.game {
+                    | Some(game) => new_name.push_str(&sanitize_inserted_name(game)),
+                    | None => new_name.push_str(fallback),
+                },
+                | TemplatePart::Users(format) =>
+                    new_name.push_str(&format.format(self.users, self.locale)),
+                | TemplatePart::Capacity(format) => match self.limited_capacity() {
+                    | Some(cap) => new_name.push_str(&format.format(cap, self.locale)),
+                    | None => new_name.push('∞'),
+                },
+                | TemplatePart::Expression { expr, format } =>
+                    match expr.evaluate(&|operand| self.value_of(operand))? {
+                        | Some(value) => {
+                            if value < 0 {
+                                new_name.push('-');
+                            }
+                            new_name.push_str(&format.format(value.unsigned_abs(), self.locale));
+                        },
+                        | None => new_name.push('∞'),
+                    },
+                | TemplatePart::Time { format } | TemplatePart::Date { format } => {
+                    let now = self.now.with_timezone(&self.time_zone);
+                    new_name.push_str(
+                        &now.format_localized(format, self.locale.time_locale())
+                            .to_string(),
+                    );
+                },
+                | TemplatePart::Weekday => {
+                    let now = self.now.with_timezone(&self.time_zone);
+                    new_name.push_str(
+                        &now.format_localized("%A", self.locale.time_locale())
+                            .to_string(),
+                    );
+                },
+                | TemplatePart::Variable(name) =>
+                    if let Some(value) = self.variables.get(name) {
+                        new_name.push_str(value);
+                    },
+                | TemplatePart::Pool(_) => match &self.pool_entry {
+                    | Some(entry) => new_name.push_str(entry),
+                    | None => new_name.push_str(&self.channel_number.to_string()),
+                },
+                | TemplatePart::Random { index, options } => {
+                    let choice = self.state.rand_choices.get(*index).copied().unwrap_or(0);
+                    if let Some(option) = options.get(choice) {
+                        new_name.push_str(option);
+                    }
+                },
+                | TemplatePart::Conditional {
+                    condition,
+                    negated,
+                    then,
+                    otherwise,
+                } =>
+                    if self.holds(*condition) == *negated {
+                        self.render_parts(otherwise, new_name)?;
+                    } else {
+                        self.render_parts(then, new_name)?;
+                    },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replaces control characters and runs of whitespace in a member or game name
+/// with single spaces and truncates it so that it can safely be embedded in a
+/// channel name.
+pub(crate) fn sanitize_inserted_name(name: &str) -> String {
+    name.split(|c: char| c.is_whitespace() || c.is_control())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_INSERTED_NAME_LENGTH)
+        .collect::<String>()
+        .trim_end()
+        .to_owned()
+}
+
+/// Picks the most common game out of the games each member is playing. Only
+/// the first game of a member counts, so that members playing several games
+/// at once don't outvote the others. Ties are broken alphabetically so that the
+/// result doesn't flip between renders.
+pub(crate) fn most_common_game<'a, G>(members: impl IntoIterator<Item = G>) -> Option<&'a str>
+where
+    G: IntoIterator<Item = &'a str>,
+{
+    let mut counts = HashMap::<&str, usize>::default();
+    for game in members
+        .into_iter()
+        .filter_map(|games| games.into_iter().next())
+    {
+        *counts.entry(game).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .max_by(|(a_name, a_count), (b_name, b_count)| {
+            a_count.cmp(b_count).then_with(|| b_name.cmp(a_name))
+        })
+        .map(|(name, _)| name)
+}
+
+/// Truncates a rendered channel name to the maximum length Discord allows.
+pub(crate) fn truncate_channel_name(name: &str) -> String {
+    name.chars()
+        .take(MAX_CHANNEL_NAME_LENGTH)
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+/// Returns the name to give a channel, which is the rendered name truncated to
+/// the length Discord allows. Discord rejects empty names, so the fallback is
+/// used instead if the rendered name is empty.
+fn channel_name(rendered: &str, fallback: &str) -> String {
+    let name = truncate_channel_name(rendered);
+    if name.is_empty() {
+        truncate_channel_name(fallback)
+    } else {
+        name
+    }
+}
+
+/// Applies rendered properties to a channel, but only those that differ from
+/// what the channel currently has. The channel is given `fallback_name` if its
+/// name renders empty.
+pub(crate) async fn update_channel(
+    context: &SerenityContext,
+    channel: &mut GuildChannel,
+    update: &ChannelUpdate,
+    fallback_name: &str,
+) -> Result<()> {
+    let new_name = channel_name(&update.name, fallback_name);
     debug!("new_name: {}", new_name,);
-    if new_name != ctx.channel.name {
-        let context = ctx.context.0.clone();
-        debug!("Cloned context.");
-        ctx.channel
-            .edit(context, EditChannel::new().name(new_name))
+    let mut edit = EditChannel::new();
+    let mut changed = false;
+    if new_name != channel.name {
+        edit = edit.name(new_name);
+        changed = true;
+    }
+    if let Some(bitrate) = update.bitrate.filter(|&b| channel.bitrate != Some(b)) {
+        edit = edit.bitrate(bitrate);
+        changed = true;
+    }
+    if let Some(user_limit) = update.user_limit.filter(|&l| channel.user_limit != Some(l)) {
+        edit = edit.user_limit(user_limit);
+        changed = true;
+    }
+    if changed {
+        channel
+            .edit(context, edit)
             .await
             .map_err(|e| eyre!(e))
-            .wrap_err_with(|| eyre!("Failed to rename channel!"))?;
+            .wrap_err_with(|| eyre!("Failed to edit channel!"))?;
+        info!("Successfully updated channel!");
+    }
+
+    if let Some(status) = update
+        .status
+        .as_deref()
+        .filter(|&status| channel.status.as_deref().unwrap_or_default() != status)
+    {
+        set_voice_status(context, channel.id, status)
+            .await
+            .wrap_err_with(|| eyre!("Failed to set status of channel {}!", channel.id))?;
+        channel.status = Some(status.to_owned());
+        info!("Successfully set channel status!");
     }
-    info!("Successfully renamed channel!");
 
     Ok(())
 }
+
+/// Sets the status shown under the name of a voice channel. An empty status
+/// clears it.
+pub(crate) async fn set_voice_status(
+    context: &SerenityContext,
+    channel_id: ChannelId,
+    status: &str,
+) -> Result<()> {
+    context
+        .http
+        .edit_voice_status(channel_id, &serde_json::json!({ "status": status }), None)
+        .await
+        .wrap_err_with(|| eyre!("Failed to send voice status request!"))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::voice_channels::parser::parse_template;
+
+    #[rstest]
+    #[case("Bwallker", "Bwallker")]
+    #[case("  spaced \t  out\n", "spaced out")]
+    #[case("a\u{0}b", "a b")]
+    #[case(
+        "abcdefghijklmnopqrstuvwxyzabcdefghijkl",
+        "abcdefghijklmnopqrstuvwxyzabcdef"
+    )]
+    fn test_sanitize_inserted_name(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(expected, sanitize_inserted_name(input));
+    }
+
+    #[rstest]
+    #[case(vec![], None)]
+    #[case(vec![vec![]], None)]
+    #[case(vec![vec!["Valorant"]], Some("Valorant"))]
+    #[case(vec![vec!["Minecraft"], vec!["Valorant"], vec!["Valorant"]], Some("Valorant"))]
+    #[case(vec![vec!["Valorant"], vec!["Minecraft"]], Some("Minecraft"))]
+    #[case(vec![vec!["Chess", "Go"], vec!["Chess", "Go"], vec!["Go"]], Some("Chess"))]
+    fn test_most_common_game(#[case] members: Vec<Vec<&str>>, #[case] expected: Option<&str>) {
+        assert_eq!(expected, most_common_game(members));
+    }
+
+    fn member(global_name: Option<&str>, nick: Option<&str>) -> Member {
+        let mut member = Member::default();
+        member.user.name = "bwallker".into();
+        member.user.global_name = global_name.map(Into::into);
+        member.nick = nick.map(Into::into);
+        member
+    }
+
+    fn owner() -> OwnerInfo {
+        OwnerInfo::from(&member(Some("Bwallker"), Some("Bw")))
+    }
+
+    fn friday_evening() -> RenderContext {
+        RenderContext {
+            now: "2024-11-01T19:30:00Z".parse().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    #[case("Gaming {#}/{%}", RenderContext::default(), "Gaming 0/0")]
+    #[case("Squad {#:roman}", RenderContext { channel_number: 4, ..Default::default() }, "Squad IV")]
+    #[case("{owner}'s room", RenderContext { owner: Some(owner()), ..Default::default() }, "Bwallker's room")]
+    #[case("{owner}'s room", RenderContext { owner: Some((&member(None, Some("Bw"))).into()), ..Default::default() }, "bwallker's room")]
+    #[case("{owner_nick}", RenderContext { owner: Some(owner()), ..Default::default() }, "Bw")]
+    #[case("{owner_nick}", RenderContext { owner: Some((&member(Some("Bwallker"), None)).into()), ..Default::default() }, "Bwallker")]
+    #[case("{game|Lobby}", RenderContext::default(), "Lobby")]
+    #[case("{game|Lobby}", RenderContext { game: Some("Valorant".into()), ..Default::default() }, "Valorant")]
+    #[case("{users}/{cap}", RenderContext { users: 2, ..Default::default() }, "2/∞")]
+    #[case("{users}/{cap}", RenderContext { users: 2, capacity: Some(0), ..Default::default() }, "2/∞")]
+    #[case("{?full:🔴|🟢}", RenderContext { users: 5, capacity: Some(5), ..Default::default() }, "🔴")]
+    #[case("{?full:🔴|🟢}", RenderContext { users: 4, capacity: Some(5), ..Default::default() }, "🟢")]
+    #[case("{?full:🔴|🟢}", RenderContext { users: 4, ..Default::default() }, "🟢")]
+    #[case("A{?!empty: ({users}/{cap})}", RenderContext { users: 1, capacity: Some(5), ..Default::default() }, "A (1/5)")]
+    #[case("A{?!empty: ({users}/{cap})}", RenderContext::default(), "A")]
+    #[case("{?game:{game}|Idle}", RenderContext { game: Some("Chess".into()), ..Default::default() }, "Chess")]
+    #[case("{weekday} Night Lobby", friday_evening(), "Friday Night Lobby")]
+    #[case("{date} {time}", friday_evening(), "2024-11-01 19:30")]
+    #[case("{time:%I:%M %p}", RenderContext { time_zone: Tz::Europe__Stockholm, ..friday_evening() }, "08:30 PM")]
+    #[case("{date:%d %b}", RenderContext { time_zone: Tz::Asia__Tokyo, ..friday_evening() }, "02 Nov")]
+    #[case("{weekday} Night Lobby", RenderContext { locale: Locale::Swedish, ..friday_evening() }, "fredag Night Lobby")]
+    #[case("{weekday}, {date:%d. %B}", RenderContext { locale: Locale::German, ..friday_evening() }, "Freitag, 01. November")]
+    #[case("{date:%d %b}", RenderContext { locale: Locale::Swedish, ..friday_evening() }, "01 nov")]
+    #[case("{$season} Lobby", RenderContext { variables: [("season".into(), "S12".into())].into_iter().collect(), ..Default::default() }, "S12 Lobby")]
+    #[case("{$season} Lobby", RenderContext::default(), " Lobby")]
+    #[case("Squad {pool:nato}", RenderContext { pool_entry: Some("Bravo".into()), ..Default::default() }, "Squad Bravo")]
+    #[case("Squad {pool:nato}", RenderContext { channel_number: 27, ..Default::default() }, "Squad 27")]
+    #[case("{rand:Cozy|Chill} Corner", RenderContext { state: RenderState { rand_choices: vec![1] }, ..Default::default() }, "Chill Corner")]
+    #[case("{?full:{rand:a|b}|{rand:c|d}}", RenderContext { state: RenderState { rand_choices: vec![0, 1] }, ..Default::default() }, "d")]
+    fn test_render(#[case] template: &str, #[case] ctx: RenderContext, #[case] expected: &str) {
+        let template = parse_template(template).unwrap();
+        assert_eq!(expected, render(&template, &ctx).unwrap());
+    }
+
+    #[rstest]
+    #[case("Room {#+100}", RenderContext { channel_number: 3, ..Default::default() }, "Room 103")]
+    #[case("{cap-users} slots left", RenderContext { users: 2, capacity: Some(5), ..Default::default() }, "3 slots left")]
+    #[case("{cap-users} slots left", RenderContext { users: 2, ..Default::default() }, "∞ slots left")]
+    #[case("{#-%:02}", RenderContext { channel_number: 1, total_children_number: 4, ..Default::default() }, "-03")]
+    fn test_render_expression(
+        #[case] template: &str,
+        #[case] ctx: RenderContext,
+        #[case] expected: &str,
+    ) {
+        let template = parse_template(template).unwrap();
+        assert_eq!(expected, render(&template, &ctx).unwrap());
+    }
+
+    #[rstest]
+    #[case("{100/(#-1)}", EvalError::DivisionByZero)]
+    #[case("{#*9223372036854775807*2}", EvalError::Overflow)]
+    fn test_render_fails(#[case] template: &str, #[case] expected: EvalError) {
+        let template = parse_template(template).unwrap();
+        let ctx = RenderContext {
+            channel_number: 1,
+            ..Default::default()
+        };
+        assert_eq!(Err(expected), render(&template, &ctx));
+    }
+
+    #[rstest]
+    #[case(None, None, None, "Lobby ∞", None, None)]
+    #[case(None, Some("64"), None, "Lobby ∞", Some(64_000), None)]
+    #[case(None, Some("1000"), None, "Lobby ∞", Some(96_000), None)]
+    #[case(None, Some("users - 10"), None, "Lobby ∞", Some(8_000), None)]
+    #[case(None, Some("cap"), None, "Lobby ∞", Some(96_000), None)]
+    #[case(None, None, Some("users + 1"), "Lobby 3", None, Some(3))]
+    #[case(None, None, Some("200"), "Lobby 99", None, Some(99))]
+    #[case(None, None, Some("-1"), "Lobby ∞", None, Some(0))]
+    #[case(Some("{users} here"), None, None, "Lobby ∞", None, None)]
+    fn test_render_channel(
+        #[case] status: Option<&str>,
+        #[case] bitrate: Option<&str>,
+        #[case] user_limit: Option<&str>,
+        #[case] name: &str,
+        #[case] expected_bitrate: Option<u32>,
+        #[case] expected_user_limit: Option<u32>,
+    ) {
+        let templates =
+            ChannelTemplates::parse("Lobby {cap}", status, bitrate, user_limit).unwrap();
+        let ctx = RenderContext {
+            users: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            ChannelUpdate {
+                name:       name.into(),
+                status:     status.map(|_| "2 here".into()),
+                bitrate:    expected_bitrate,
+                user_limit: expected_user_limit,
+            },
+            render_channel(&templates, &ChannelOverrides::default(), ctx, 96).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case("Lobby", None, false)]
+    #[case("Lobby {time}", None, true)]
+    #[case("Lobby", Some("Since {time}"), true)]
+    #[case("Lobby", Some("{users} here"), false)]
+    fn test_channel_templates_any(
+        #[case] name: &str,
+        #[case] status: Option<&str>,
+        #[case] expected: bool,
+    ) {
+        let templates = ChannelTemplates::parse(name, status, None, None).unwrap();
+        assert_eq!(expected, templates.any(Template::is_time_dependent));
+    }
+
+    #[rstest]
+    #[case(ChannelOverrides::default(), "Lobby 5", Some(64_000), Some(5))]
+    #[case(ChannelOverrides { name: Some("Quiet room".into()), ..Default::default() }, "Quiet room", Some(64_000), Some(5))]
+    #[case(ChannelOverrides { user_limit: Some(3), ..Default::default() }, "Lobby 3", Some(64_000), Some(3))]
+    #[case(ChannelOverrides { user_limit: Some(150), ..Default::default() }, "Lobby 99", Some(64_000), Some(99))]
+    #[case(ChannelOverrides { bitrate: Some(200), ..Default::default() }, "Lobby 5", Some(96_000), Some(5))]
+    #[case(ChannelOverrides { bitrate: Some(1), ..Default::default() }, "Lobby 5", Some(8_000), Some(5))]
+    fn test_render_channel_overrides(
+        #[case] overrides: ChannelOverrides,
+        #[case] name: &str,
+        #[case] expected_bitrate: Option<u32>,
+        #[case] expected_user_limit: Option<u32>,
+    ) {
+        let templates =
+            ChannelTemplates::parse("Lobby {cap}", None, Some("64"), Some("5")).unwrap();
+        assert_eq!(
+            ChannelUpdate {
+                name:       name.into(),
+                status:     None,
+                bitrate:    expected_bitrate,
+                user_limit: expected_user_limit,
+            },
+            render_channel(&templates, &overrides, RenderContext::default(), 96).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_reconcile_render_state() {
+        let template = parse_template("{rand:a|b|c} {rand:d|e} {rand:f}").unwrap();
+        let mut state = RenderState {
+            rand_choices: vec![2, 5],
+        };
+        assert!(state.reconcile(&template));
+        assert_eq!(3, state.rand_choices.len());
+        assert_eq!(2, state.rand_choices[0]);
+        assert!(state.rand_choices[1] < 2);
+        assert_eq!(0, state.rand_choices[2]);
+        assert!(!state.clone().reconcile(&template));
+    }
+
+    #[rstest]
+    #[case("Squad 1", "Squad 1")]
+    #[case("", "Lobby")]
+    #[case("   ", "Lobby")]
+    fn test_channel_name(#[case] rendered: &str, #[case] expected: &str) {
+        assert_eq!(expected, channel_name(rendered, "Lobby"));
+    }
+
+    #[test]
+    fn test_truncate_channel_name() {
+        let name = "å".repeat(MAX_CHANNEL_NAME_LENGTH + 10);
+        assert_eq!(
+            MAX_CHANNEL_NAME_LENGTH,
+            truncate_channel_name(&name).chars().count()
+        );
+    }
+}